@@ -0,0 +1,51 @@
+use criterion::{
+  criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion,
+};
+use snake_ctrl::{Direction, Point, SnakeCtrl, SnakeCtrlOptions};
+
+/// A board of `dim` x `dim` with the lower half covered by one snake going
+/// back and forth. Its head looks up into the free upper half.
+fn half_full_board(dim: u16) -> SnakeCtrl {
+  let rows = dim / 2;
+  let mut body = vec![];
+  let mut go_right = true;
+  for y in (0..rows).rev() {
+    for i in 0..dim {
+      let x = if go_right { i } else { dim - 1 - i };
+      body.push(Point(x, y));
+    }
+    go_right = !go_right;
+  }
+  let opts = SnakeCtrlOptions::default()
+    .dimension_x(dim)
+    .dimension_y(dim)
+    .auto_gen_food(false)
+    .start_head(Point(0, rows))
+    .start_direction(Direction::Top)
+    .start_body(body);
+  SnakeCtrl::new(&opts).unwrap()
+}
+
+fn ticks(c: &mut Criterion) {
+  let mut group = c.benchmark_group("tick");
+  for dim in [100u16, 200, 400] {
+    let ctrl = half_full_board(dim);
+    let steps = dim / 2 - 1;
+    group.bench_with_input(BenchmarkId::from_parameter(dim), &ctrl, |b, g| {
+      b.iter_batched(
+        || g.fork(),
+        |mut game| {
+          for _ in 0..steps {
+            game.next_tick().unwrap();
+          }
+          game
+        },
+        BatchSize::LargeInput,
+      )
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, ticks);
+criterion_main!(benches);
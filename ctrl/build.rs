@@ -0,0 +1,24 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Regenerates the C header when `SNAKE_CTRL_GEN_HEADER` is set. The
+/// header is kept in git, so normal builds leave the source tree alone.
+fn main() {
+  println!("cargo:rerun-if-env-changed=SNAKE_CTRL_GEN_HEADER");
+  if env::var_os("SNAKE_CTRL_GEN_HEADER").is_none() {
+    return;
+  }
+  println!("cargo:rerun-if-changed=src");
+  println!("cargo:rerun-if-changed=cbindgen.toml");
+
+  let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+  let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+    .expect("cbindgen.toml is invalid");
+
+  cbindgen::Builder::new()
+    .with_crate(&crate_dir)
+    .with_config(config)
+    .generate()
+    .expect("unable to generate C bindings")
+    .write_to_file(crate_dir.join("include").join("snake_ctrl.h"));
+}
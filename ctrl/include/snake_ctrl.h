@@ -0,0 +1,486 @@
+/* Generated by cbindgen from snake_ctrl. Do not edit. */
+
+#ifndef SNAKE_CTRL_H
+#define SNAKE_CTRL_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define Features_SIZE 11
+
+/**
+ * Bumped on every incompatible change of the messages below. Peers with a
+ * different version are rejected on `Hello`.
+ */
+#define PROTOCOL_VERSION 4
+
+/**
+ * A turn relative to where the head looks.
+ */
+typedef enum SnakeCtrlAction {
+  SnakeCtrlAction_Left,
+  SnakeCtrlAction_Straight,
+  SnakeCtrlAction_Right,
+} SnakeCtrlAction;
+
+typedef enum DeathCause {
+  DeathCause_AteItself,
+  DeathCause_HitTheWall,
+  /**
+   * The head ran into the body of another snake.
+   */
+  DeathCause_HitSnake,
+  /**
+   * Two heads moved into the same cell.
+   */
+  DeathCause_HeadToHead,
+  /**
+   * The head ran into an obstacle inside the board.
+   */
+  DeathCause_HitObstacle,
+  /**
+   * The snake ate poison.
+   */
+  DeathCause_Poisoned,
+} DeathCause;
+
+/**
+ * What happens to a snake that dies while others are still playing.
+ */
+typedef enum DeathRule {
+  /**
+   * The whole game is over.
+   */
+  DeathRule_EndGame,
+  /**
+   * The snake leaves the board, the rest keep playing.
+   */
+  DeathRule_Remove,
+  /**
+   * The snake leaves the board and its body turns into food.
+   */
+  DeathRule_TurnIntoFood,
+} DeathRule;
+
+typedef enum Direction {
+  Direction_Top,
+  Direction_Right,
+  Direction_Bottom,
+  Direction_Left,
+} Direction;
+
+/**
+ * What lies on the board and what it does to the snake that eats it.
+ */
+typedef enum FoodKind {
+  /**
+   * One cell longer, one point.
+   */
+  FoodKind_Apple,
+  /**
+   * One cell longer, five points.
+   */
+  FoodKind_Golden,
+  /**
+   * Two cells shorter, down to two cells.
+   */
+  FoodKind_Shrink,
+  /**
+   * One cell longer, the game should make ticks shorter.
+   */
+  FoodKind_SpeedUp,
+  /**
+   * One cell longer, the game should make ticks longer.
+   */
+  FoodKind_SlowDown,
+  /**
+   * Kills the snake.
+   */
+  FoodKind_Poison,
+} FoodKind;
+
+typedef enum SnakeCornerVariant {
+  SnakeCornerVariant_TopLeft,
+  SnakeCornerVariant_TopRight,
+  SnakeCornerVariant_BottomLeft,
+  SnakeCornerVariant_BottomRight,
+} SnakeCornerVariant;
+
+typedef enum SnakeCtrlCPartKind {
+  SnakeCtrlCPartKind_Head,
+  SnakeCtrlCPartKind_Tail,
+  SnakeCtrlCPartKind_Body,
+  SnakeCtrlCPartKind_Corner,
+} SnakeCtrlCPartKind;
+
+typedef enum SnakeCtrlCode {
+  SnakeCtrlCode_Ok = 0,
+  SnakeCtrlCode_RowIndexOutOfBounds = 1,
+  SnakeCtrlCode_ColumnIndexOutOfBounds = 2,
+  SnakeCtrlCode_InitSnakeSizeIsBig = 3,
+  SnakeCtrlCode_SnakeIsZero = 4,
+  SnakeCtrlCode_GameIsOver = 5,
+  SnakeCtrlCode_SnapshotVersion = 6,
+  SnakeCtrlCode_UnknownPlayer = 7,
+  SnakeCtrlCode_TooManyPlayers = 8,
+  SnakeCtrlCode_SnakeBlocked = 9,
+  SnakeCtrlCode_SpawnBlocked = 10,
+  SnakeCtrlCode_FoodBlocked = 11,
+  SnakeCtrlCode_BadPortal = 12,
+  SnakeCtrlCode_BrokenSnake = 13,
+  SnakeCtrlCode_NullPointer = 100,
+  SnakeCtrlCode_BufferTooSmall = 101,
+  SnakeCtrlCode_Panic = 102,
+  /**
+   * An enum argument is not one of its values.
+   */
+  SnakeCtrlCode_InvalidValue = 103,
+} SnakeCtrlCode;
+
+/**
+ * Observation encoder of a `SnakeCtrlEnv`.
+ */
+typedef enum SnakeCtrlEnvEncoder {
+  /**
+   * `Features::SIZE` flags.
+   */
+  SnakeCtrlEnvEncoder_Features,
+  /**
+   * One-hot planes of the board, see `Matrix::one_hot`.
+   */
+  SnakeCtrlEnvEncoder_GridPlanes,
+} SnakeCtrlEnvEncoder;
+
+typedef struct SnakeCtrlEnv SnakeCtrlEnv;
+
+/**
+ * A game. Cloning it is cheap, the clone plays on by itself from the same
+ * state with no observers and no recording.
+ */
+typedef struct SnakeCtrl SnakeCtrl;
+
+typedef struct Point {
+  uint16_t _0;
+  uint16_t _1;
+} Point;
+
+typedef struct SnakeCtrlCOptions {
+  uint16_t dimension_x;
+  uint16_t dimension_y;
+  uint16_t initial_snake_size;
+  bool walking_through_the_walls;
+  bool fail_on_revert;
+  bool auto_gen_food;
+  /**
+   * When false a random seed is picked and `seed` is ignored.
+   */
+  bool use_seed;
+  uint64_t seed;
+  uint8_t players;
+  /**
+   * A `DeathRule`.
+   */
+  uint32_t death_rule;
+  /**
+   * Obstacle cells, `walls_len` points. May be null when `walls_len` is 0.
+   */
+  const struct Point *walls;
+  uintptr_t walls_len;
+  /**
+   * Cells a snake gets for one food.
+   */
+  uint16_t growth_per_food;
+  /**
+   * Turns kept for the next ticks.
+   */
+  uint8_t input_queue_depth;
+} SnakeCtrlCOptions;
+
+/**
+ * Summary of one tick for the first player. The points are meaningful
+ * only when the matching flag is set.
+ */
+typedef struct SnakeCtrlCTick {
+  struct Point head;
+  uintptr_t length;
+  bool is_teleported;
+  bool is_ate;
+  struct Point eaten;
+  enum FoodKind eaten_kind;
+  bool is_food_spawned;
+  struct Point spawned;
+  bool is_dead;
+  enum DeathCause death_cause;
+  struct Point death_point;
+  bool is_won;
+} SnakeCtrlCTick;
+
+typedef struct SnakeCtrlCState {
+  struct Point *snake;
+  uintptr_t snake_len;
+  struct Point *food;
+  uintptr_t food_len;
+  enum Direction head_direction;
+  enum Direction tail_direction;
+} SnakeCtrlCState;
+
+/**
+ * One snake segment. `direction` is meaningful for `Head` and `Tail`,
+ * `is_vertical` for `Body` and `corner` for `Corner`.
+ */
+typedef struct SnakeCtrlCPart {
+  struct Point point;
+  enum SnakeCtrlCPartKind kind;
+  enum Direction direction;
+  bool is_vertical;
+  enum SnakeCornerVariant corner;
+} SnakeCtrlCPart;
+
+/**
+ * `food_kinds` has `food_len` items, one for every point of `food`.
+ */
+typedef struct SnakeCtrlCFullState {
+  struct SnakeCtrlCPart *snake;
+  uintptr_t snake_len;
+  struct Point *food;
+  enum FoodKind *food_kinds;
+  uintptr_t food_len;
+  enum Direction direction;
+} SnakeCtrlCFullState;
+
+/**
+ * Reward shaping of `Env::step`, the rewards of a step are summed up.
+ */
+typedef struct SnakeCtrlRewards {
+  /**
+   * Per point of score the eaten food gives.
+   */
+  float food;
+  float death;
+  /**
+   * Every step, a small negative value hurries the snake.
+   */
+  float step;
+  float win;
+  /**
+   * Given when the head gets closer to the nearest food and taken when it
+   * gets further.
+   */
+  float approach;
+} SnakeCtrlRewards;
+
+/**
+ * Result of one `snake_ctrl_env_step`. `death_cause` is meaningful only
+ * when `is_dead` is set.
+ */
+typedef struct SnakeCtrlCStep {
+  float reward;
+  bool done;
+  bool truncated;
+  uint64_t score;
+  uintptr_t length;
+  uint64_t ticks;
+  bool ate;
+  bool is_dead;
+  enum DeathCause death_cause;
+  bool is_won;
+} SnakeCtrlCStep;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct SnakeCtrlCOptions snake_ctrl_options_default(void);
+
+/**
+ * Static, NUL-terminated description of `code`, a `SnakeCtrlCode`. Other
+ * values get a description too.
+ */
+const char *snake_ctrl_code_message(uint32_t code);
+
+/**
+ * # Safety
+ * `opts` must be null or point to valid options with valid `walls`, `out`
+ * must be a valid pointer. On success `*out` holds a handle to release
+ * with `snake_ctrl_free`.
+ */
+enum SnakeCtrlCode snake_ctrl_new(const struct SnakeCtrlCOptions *opts, struct SnakeCtrl **out);
+
+/**
+ * # Safety
+ * `ctrl` must be null or a handle from `snake_ctrl_new` not freed yet.
+ */
+void snake_ctrl_free(struct SnakeCtrl *ctrl);
+
+/**
+ * `direction` is a `Direction`, other values give `InvalidValue`.
+ *
+ * # Safety
+ * `ctrl` must be a live handle.
+ */
+enum SnakeCtrlCode snake_ctrl_direction_to(struct SnakeCtrl *ctrl, uint32_t direction);
+
+/**
+ * `direction` is a `Direction`, other values give `InvalidValue`.
+ *
+ * # Safety
+ * `ctrl` must be a live handle.
+ */
+enum SnakeCtrlCode snake_ctrl_direction_to_player(struct SnakeCtrl *ctrl,
+                                                  uintptr_t player,
+                                                  uint32_t direction);
+
+/**
+ * Moves the snake one cell. `tick` may be null, otherwise it receives
+ * what happened during the tick. Dying or winning is not an error, the
+ * next call after it returns `GameIsOver`.
+ *
+ * # Safety
+ * `ctrl` must be a live handle, `tick` null or valid.
+ */
+enum SnakeCtrlCode snake_ctrl_next_tick(struct SnakeCtrl *ctrl, struct SnakeCtrlCTick *tick);
+
+/**
+ * # Safety
+ * `ctrl` must be a live handle.
+ */
+enum SnakeCtrlCode snake_ctrl_restart(struct SnakeCtrl *ctrl);
+
+/**
+ * Restarts the game with an explicit food seed.
+ *
+ * # Safety
+ * `ctrl` must be a live handle.
+ */
+enum SnakeCtrlCode snake_ctrl_restart_with_seed(struct SnakeCtrl *ctrl, uint64_t seed);
+
+/**
+ * # Safety
+ * `ctrl` must be a live handle, `seed` a valid pointer.
+ */
+enum SnakeCtrlCode snake_ctrl_seed(const struct SnakeCtrl *ctrl, uint64_t *seed);
+
+/**
+ * # Safety
+ * `ctrl` must be a live handle, `direction` a valid pointer.
+ */
+enum SnakeCtrlCode snake_ctrl_current_direction(const struct SnakeCtrl *ctrl,
+                                                enum Direction *direction);
+
+/**
+ * Fills `out` with a copy of the state. Release it with
+ * `snake_ctrl_state_free`.
+ *
+ * # Safety
+ * `ctrl` must be a live handle, `out` a valid pointer.
+ */
+enum SnakeCtrlCode snake_ctrl_get_state(const struct SnakeCtrl *ctrl,
+                                        bool reversed_y,
+                                        struct SnakeCtrlCState *out);
+
+/**
+ * # Safety
+ * `state` must be null or filled by `snake_ctrl_get_state`.
+ */
+void snake_ctrl_state_free(struct SnakeCtrlCState *state);
+
+/**
+ * Fills `out` with the segment-by-segment state. Release it with
+ * `snake_ctrl_full_state_free`.
+ *
+ * # Safety
+ * `ctrl` must be a live handle, `out` a valid pointer.
+ */
+enum SnakeCtrlCode snake_ctrl_get_full_state(const struct SnakeCtrl *ctrl,
+                                             bool reversed_y,
+                                             struct SnakeCtrlCFullState *out);
+
+/**
+ * # Safety
+ * `state` must be null or filled by `snake_ctrl_get_full_state`.
+ */
+void snake_ctrl_full_state_free(struct SnakeCtrlCFullState *state);
+
+/**
+ * Copies the matrix row by row from `y = 0` into `buf` (`dimension_x *
+ * dimension_y` cells: 0 empty, 1 snake, 7 apple, 8 wall, 9 golden apple,
+ * 10 shrink, 11 speed up, 12 slow down, 13 poison). The dimensions are written to
+ * `dim_x` and `dim_y` when they are not null, so a call with a null `buf`
+ * can be used to size the buffer.
+ *
+ * # Safety
+ * `ctrl` must be a live handle, `buf` null or valid for `buf_len` bytes.
+ */
+enum SnakeCtrlCode snake_ctrl_get_matrix(const struct SnakeCtrl *ctrl,
+                                         uint8_t *buf,
+                                         uintptr_t buf_len,
+                                         uint16_t *dim_x,
+                                         uint16_t *dim_y);
+
+struct SnakeCtrlRewards snake_ctrl_rewards_default(void);
+
+/**
+ * Creates a training environment. `opts` and `rewards` may be null for
+ * the defaults, `max_ticks` of 0 never truncates an episode. `encoder`
+ * is a `SnakeCtrlEnvEncoder`, other values give `InvalidValue`.
+ *
+ * # Safety
+ * `opts` and `rewards` must be null or valid, `out` a valid pointer. On
+ * success `*out` holds a handle to release with `snake_ctrl_env_free`.
+ */
+enum SnakeCtrlCode snake_ctrl_env_new(const struct SnakeCtrlCOptions *opts,
+                                      uint32_t encoder,
+                                      const struct SnakeCtrlRewards *rewards,
+                                      uint64_t max_ticks,
+                                      struct SnakeCtrlEnv **out);
+
+/**
+ * # Safety
+ * `env` must be null or a handle from `snake_ctrl_env_new` not freed yet.
+ */
+void snake_ctrl_env_free(struct SnakeCtrlEnv *env);
+
+/**
+ * Number of floats in every observation of `env`.
+ *
+ * # Safety
+ * `env` must be a live handle, `size` a valid pointer.
+ */
+enum SnakeCtrlCode snake_ctrl_env_observation_size(const struct SnakeCtrlEnv *env, uintptr_t *size);
+
+/**
+ * Starts an episode on `seed` and copies its first observation into
+ * `obs` when it is not null.
+ *
+ * # Safety
+ * `env` must be a live handle, `obs` null or valid for `obs_len` floats.
+ */
+enum SnakeCtrlCode snake_ctrl_env_reset(struct SnakeCtrlEnv *env,
+                                        uint64_t seed,
+                                        float *obs,
+                                        uintptr_t obs_len);
+
+/**
+ * Plays one action. The observation goes into `obs` when it is not null
+ * and the rest into `step` when it is not null. A too small `obs` is
+ * reported before the game moves. Once `done` is set the next call
+ * returns `GameIsOver` until `snake_ctrl_env_reset`. `action` is a
+ * `SnakeCtrlAction`, other values give `InvalidValue`.
+ *
+ * # Safety
+ * `env` must be a live handle, `obs` null or valid for `obs_len` floats,
+ * `step` null or valid.
+ */
+enum SnakeCtrlCode snake_ctrl_env_step(struct SnakeCtrlEnv *env,
+                                       uint32_t action,
+                                       float *obs,
+                                       uintptr_t obs_len,
+                                       struct SnakeCtrlCStep *step);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SNAKE_CTRL_H */
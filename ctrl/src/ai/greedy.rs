@@ -0,0 +1,22 @@
+use crate::ai::{safe_moves, survive, Grid};
+use crate::{Direction, Observation, Policy};
+
+/// Heads to the nearest food by the shortest distance, avoiding only the
+/// moves that kill the snake right away.
+#[derive(Default)]
+pub struct Greedy {}
+
+impl Policy for Greedy {
+  fn decide(&mut self, obs: &Observation) -> Direction {
+    let grid = Grid::from_obs(obs);
+    let blocked = grid.blocked_by(&obs.snake, &obs.obstacles());
+    let head = obs.head();
+
+    let food_distance = |p| obs.food.iter().map(|f| grid.distance(p, *f)).min();
+    safe_moves(obs, &grid, &blocked)
+      .into_iter()
+      .min_by_key(|p| food_distance(*p))
+      .and_then(|p| grid.direction_between(head, p))
+      .unwrap_or_else(|| survive(obs, &grid, &blocked))
+  }
+}
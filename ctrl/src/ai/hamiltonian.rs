@@ -0,0 +1,100 @@
+use crate::ai::{Grid, PathFinder};
+use crate::{Direction, Observation, Point, Policy};
+
+/// Walks a cycle through every cell of the board, so it never dies and
+/// always fills the board in the end. A solid board needs an even side for
+/// such a cycle; on odd boards without wrapping through the left and right
+/// edges it falls back to `PathFinder`.
+#[derive(Default)]
+pub struct Hamiltonian {
+  cycle: Option<Cycle>,
+  fallback: PathFinder,
+}
+
+struct Cycle {
+  grid: Grid,
+  cells: Vec<Point>,
+  position: Vec<usize>,
+}
+
+impl Cycle {
+  fn build(grid: Grid) -> Option<Self> {
+    let (dim_x, dim_y) = (grid.dim_x, grid.dim_y);
+    if dim_x < 2 || dim_y < 2 {
+      return None;
+    }
+
+    let wraps_x = grid.wrap.left && grid.wrap.right;
+    let cells = if dim_y % 2 == 0 || wraps_x {
+      Cycle::zigzag(dim_x, dim_y)
+    } else if dim_x % 2 == 0 {
+      Cycle::zigzag(dim_y, dim_x)
+        .into_iter()
+        .map(|Point(x, y)| Point(y, x))
+        .collect()
+    } else {
+      return None;
+    };
+
+    let mut position = vec![0; grid.len()];
+    for (ind, p) in cells.iter().enumerate() {
+      position[grid.index(*p)] = ind;
+    }
+    Some(Cycle {
+      grid,
+      cells,
+      position,
+    })
+  }
+
+  /// Rows are walked back and forth over the columns `1..dim_x` and the
+  /// column `0` leads back to the start. With an odd number of rows the
+  /// last row ends on the right edge, which wraps to the column `0`.
+  fn zigzag(dim_x: u16, dim_y: u16) -> Vec<Point> {
+    let mut cells = Vec::with_capacity(usize::from(dim_x) * usize::from(dim_y));
+    for y in 0..dim_y {
+      if y % 2 == 0 {
+        cells.extend((1..dim_x).map(|x| Point(x, y)));
+      } else {
+        cells.extend((1..dim_x).rev().map(|x| Point(x, y)));
+      }
+    }
+    cells.extend((0..dim_y).rev().map(|y| Point(0, y)));
+    cells
+  }
+
+  fn next(&self, p: Point, forward: bool) -> Point {
+    let len = self.cells.len();
+    let ind = self.position[self.grid.index(p)];
+    if forward {
+      self.cells[(ind + 1) % len]
+    } else {
+      self.cells[(ind + len - 1) % len]
+    }
+  }
+}
+
+impl Policy for Hamiltonian {
+  fn decide(&mut self, obs: &Observation) -> Direction {
+    let grid = Grid::from_obs(obs);
+    if self.cycle.as_ref().map(|c| c.grid) != Some(grid) {
+      self.cycle = Cycle::build(grid);
+    }
+    let cycle = match &self.cycle {
+      Some(c) => c,
+      None => return self.fallback.decide(obs),
+    };
+
+    let head = obs.head();
+    let forward = cycle.next(head, true) != obs.snake[1];
+    let target = cycle.next(head, forward);
+    let blocked = grid.blocked_by(&obs.snake, &obs.obstacles());
+
+    if blocked[grid.index(target)] {
+      return self.fallback.decide(obs);
+    }
+    grid
+      .direction_between(head, target)
+      .unwrap_or_else(|| self.fallback.decide(obs))
+  }
+}
@@ -0,0 +1,275 @@
+//! Reference autopilots. All of them are `Policy`s, so they can drive a
+//! `Simulator` or be asked for a direction before every `next_tick`.
+
+mod greedy;
+mod hamiltonian;
+mod path;
+
+pub use greedy::Greedy;
+pub use hamiltonian::Hamiltonian;
+pub use path::PathFinder;
+
+use crate::{Direction, Observation, Point, Policy, WrapEdges};
+use std::collections::VecDeque;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AiKind {
+  Greedy,
+  PathFinder,
+  Hamiltonian,
+}
+
+impl AiKind {
+  pub fn policy(self) -> Box<dyn Policy> {
+    match self {
+      AiKind::Greedy => Box::new(Greedy::default()),
+      AiKind::PathFinder => Box::new(PathFinder::default()),
+      AiKind::Hamiltonian => Box::new(Hamiltonian::default()),
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      AiKind::Greedy => "greedy",
+      AiKind::PathFinder => "path finder",
+      AiKind::Hamiltonian => "hamiltonian",
+    }
+  }
+}
+
+pub(crate) static DIRECTIONS: [Direction; 4] = [
+  Direction::Top,
+  Direction::Right,
+  Direction::Bottom,
+  Direction::Left,
+];
+
+/// Board topology as the engine sees it, with wrapping through the edges
+/// the snake can walk through.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Grid {
+  dim_x: u16,
+  dim_y: u16,
+  wrap: WrapEdges,
+}
+
+impl Grid {
+  pub(crate) fn from_obs(obs: &Observation) -> Self {
+    Grid {
+      dim_x: obs.dimension_x,
+      dim_y: obs.dimension_y,
+      wrap: obs.wrap,
+    }
+  }
+
+  fn len(&self) -> usize {
+    usize::from(self.dim_x) * usize::from(self.dim_y)
+  }
+
+  pub(crate) fn index(&self, p: Point) -> usize {
+    usize::from(p.1) * usize::from(self.dim_x) + usize::from(p.0)
+  }
+
+  fn point(&self, ind: usize) -> Point {
+    let dim_x = usize::from(self.dim_x);
+    Point((ind % dim_x) as u16, (ind / dim_x) as u16)
+  }
+
+  pub(crate) fn step(&self, p: Point, direction: Direction) -> Option<Point> {
+    let (x, y) = (i32::from(p.0), i32::from(p.1));
+    let (x, y) = match direction {
+      Direction::Right => (x + 1, y),
+      Direction::Top => (x, y + 1),
+      Direction::Bottom => (x, y - 1),
+      Direction::Left => (x - 1, y),
+    };
+    let (dim_x, dim_y) = (i32::from(self.dim_x), i32::from(self.dim_y));
+    let wraps = if x < 0 {
+      self.wrap.left
+    } else if x >= dim_x {
+      self.wrap.right
+    } else if y < 0 {
+      self.wrap.bottom
+    } else if y >= dim_y {
+      self.wrap.top
+    } else {
+      true
+    };
+    if !wraps {
+      return None;
+    }
+    Some(Point(
+      x.rem_euclid(dim_x) as u16,
+      y.rem_euclid(dim_y) as u16,
+    ))
+  }
+
+  fn direction_between(&self, from: Point, to: Point) -> Option<Direction> {
+    DIRECTIONS
+      .iter()
+      .copied()
+      .find(|d| self.step(from, *d) == Some(to))
+  }
+
+  /// Shortcuts through the edges are taken only when both edges of the
+  /// axis let the snake through.
+  fn axis_distance(&self, a: u16, b: u16, dim: u16, wrap: bool) -> u32 {
+    let d = u32::from(a.abs_diff(b));
+    if wrap {
+      d.min(u32::from(dim) - d)
+    } else {
+      d
+    }
+  }
+
+  pub(crate) fn distance(&self, a: Point, b: Point) -> u32 {
+    let WrapEdges {
+      left,
+      right,
+      top,
+      bottom,
+    } = self.wrap;
+    self.axis_distance(a.0, b.0, self.dim_x, left && right)
+      + self.axis_distance(a.1, b.1, self.dim_y, top && bottom)
+  }
+
+  /// Cells taken by the snake and by the obstacles. The tail is left free,
+  /// it moves away during the same tick.
+  pub(crate) fn blocked_by(
+    &self,
+    snake: &[Point],
+    obstacles: &[Point],
+  ) -> Vec<bool> {
+    let mut blocked = vec![false; self.len()];
+    for p in snake[..snake.len() - 1].iter().chain(obstacles) {
+      blocked[self.index(*p)] = true;
+    }
+    blocked
+  }
+
+  /// Shortest path to the first cell matching `is_target`, without `from`.
+  fn bfs<F: Fn(Point) -> bool>(
+    &self,
+    blocked: &[bool],
+    from: Point,
+    is_target: F,
+  ) -> Option<Vec<Point>> {
+    let mut came_from: Vec<Option<usize>> = vec![None; self.len()];
+    let start = self.index(from);
+    came_from[start] = Some(start);
+    let mut queue = VecDeque::new();
+    queue.push_back(from);
+
+    while let Some(p) = queue.pop_front() {
+      if p != from && is_target(p) {
+        let mut path = vec![p];
+        let mut ind = self.index(p);
+        while let Some(prev) = came_from[ind].filter(|prev| *prev != start) {
+          path.push(self.point(prev));
+          ind = prev;
+        }
+        path.reverse();
+        return Some(path);
+      }
+      for d in &DIRECTIONS {
+        if let Some(n) = self.step(p, *d) {
+          let ind = self.index(n);
+          if came_from[ind].is_none() && !blocked[ind] {
+            came_from[ind] = Some(self.index(p));
+            queue.push_back(n);
+          }
+        }
+      }
+    }
+    None
+  }
+
+  /// Number of free cells reachable from `from`.
+  fn reachable_area(&self, blocked: &[bool], from: Point) -> usize {
+    let mut seen = blocked.to_vec();
+    let mut stack = vec![from];
+    seen[self.index(from)] = true;
+    let mut area = 0;
+    while let Some(p) = stack.pop() {
+      area += 1;
+      for d in &DIRECTIONS {
+        if let Some(n) = self.step(p, *d) {
+          let ind = self.index(n);
+          if !seen[ind] {
+            seen[ind] = true;
+            stack.push(n);
+          }
+        }
+      }
+    }
+    area
+  }
+}
+
+/// Moves that do not kill the snake right away.
+fn safe_moves(obs: &Observation, grid: &Grid, blocked: &[bool]) -> Vec<Point> {
+  let head = obs.head();
+  let mut moves: Vec<(Direction, Point)> = DIRECTIONS
+    .iter()
+    .filter(|d| **d != obs.direction.opposite_direction())
+    .filter_map(|d| grid.step(head, *d).map(|p| (*d, p)))
+    .filter(|(_, p)| !blocked[grid.index(*p)])
+    .collect();
+  moves.sort_by_key(|(d, _)| *d != obs.direction);
+  moves.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The move that keeps the most room, or straight ahead when every move
+/// is deadly.
+fn survive(obs: &Observation, grid: &Grid, blocked: &[bool]) -> Direction {
+  let head = obs.head();
+  safe_moves(obs, grid, blocked)
+    .into_iter()
+    .max_by_key(|p| grid.reachable_area(blocked, *p))
+    .and_then(|p| grid.direction_between(head, p))
+    .unwrap_or(obs.direction)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ai::AiKind;
+  use crate::{SimSummary, Simulator, SnakeCtrlOptions};
+
+  fn summary(kind: AiKind, opts: &SnakeCtrlOptions) -> SimSummary {
+    let stats = Simulator::new(opts)
+      .games(8)
+      .max_ticks(5_000)
+      .run(|_| kind.policy())
+      .unwrap();
+    SimSummary::from_stats(&stats)
+  }
+
+  #[test]
+  fn greedy_and_path_finder_eat() {
+    for wrap in &[true, false] {
+      let opts = SnakeCtrlOptions::default()
+        .dimension_x(12)
+        .dimension_y(12)
+        .walking_through_the_walls(*wrap);
+      let greedy = summary(AiKind::Greedy, &opts);
+      let path = summary(AiKind::PathFinder, &opts);
+      assert!(greedy.mean_score >= 5.0);
+      assert!(path.mean_score >= greedy.mean_score);
+    }
+  }
+
+  #[test]
+  fn hamiltonian_fills_the_board() {
+    let solid = SnakeCtrlOptions::default()
+      .dimension_x(8)
+      .dimension_y(6)
+      .walking_through_the_walls(false);
+    assert_eq!(summary(AiKind::Hamiltonian, &solid).wins, 8);
+
+    let odd_torus = SnakeCtrlOptions::default()
+      .dimension_x(7)
+      .dimension_y(7)
+      .walking_through_the_walls(true);
+    assert_eq!(summary(AiKind::Hamiltonian, &odd_torus).wins, 8);
+  }
+}
@@ -0,0 +1,53 @@
+use crate::ai::{survive, Grid};
+use crate::{Direction, Observation, Point, Policy};
+
+/// Follows the shortest path to food, but only when the snake can still
+/// reach its own tail after eating. Otherwise it chases the tail, and if
+/// that fails too it moves where there is the most room.
+#[derive(Default)]
+pub struct PathFinder {}
+
+impl PathFinder {
+  fn is_safe_to_follow(
+    grid: &Grid,
+    snake: &[Point],
+    obstacles: &[Point],
+    path: &[Point],
+  ) -> bool {
+    let mut future: Vec<Point> = path.iter().rev().copied().collect();
+    future.extend_from_slice(snake);
+    future.truncate(snake.len() + 1);
+
+    let head = future[0];
+    let tail = future[future.len() - 1];
+    let blocked = grid.blocked_by(&future, obstacles);
+    grid.bfs(&blocked, head, |p| p == tail).is_some()
+  }
+}
+
+impl Policy for PathFinder {
+  fn decide(&mut self, obs: &Observation) -> Direction {
+    let grid = Grid::from_obs(obs);
+    let obstacles = obs.obstacles();
+    let blocked = grid.blocked_by(&obs.snake, &obstacles);
+    let head = obs.head();
+    let tail = obs.snake[obs.snake.len() - 1];
+    let neck = obs.snake[1];
+
+    let to_food = grid
+      .bfs(&blocked, head, |p| obs.food.contains(&p))
+      .filter(|path| path[0] != neck)
+      .filter(|path| {
+        PathFinder::is_safe_to_follow(&grid, &obs.snake, &obstacles, path)
+      });
+    let path = to_food.or_else(|| {
+      grid
+        .bfs(&blocked, head, |p| p == tail)
+        .filter(|path| path[0] != neck)
+    });
+
+    path
+      .and_then(|path| grid.direction_between(head, path[0]))
+      .unwrap_or_else(|| survive(obs, &grid, &blocked))
+  }
+}
@@ -0,0 +1,54 @@
+use snake_ctrl::net::{Server, ServerOptions};
+use snake_ctrl::SnakeCtrlOptions;
+use std::process;
+
+static USAGE: &str = "usage: snake_server [--addr host:port] [--players N] \
+                      [--size WxH] [--tick-ms MS]";
+
+fn parse_args() -> Result<(String, ServerOptions), String> {
+  let mut addr = "0.0.0.0:7878".to_owned();
+  let mut game = SnakeCtrlOptions::default()
+    .dimension_x(27)
+    .dimension_y(23)
+    .initial_snake_size(5)
+    .input_queue_depth(3)
+    .players(2);
+  let mut tick_millis = 150;
+
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let val = args
+      .next()
+      .ok_or_else(|| format!("{} needs a value", arg))?;
+    let bad = || format!("bad value for {}: {}", arg, val);
+    match arg.as_str() {
+      "--addr" => addr = val.clone(),
+      "--players" => game = game.players(val.parse().map_err(|_| bad())?),
+      "--tick-ms" => tick_millis = val.parse().map_err(|_| bad())?,
+      "--size" => {
+        let (x, y) = val.split_once('x').ok_or_else(bad)?;
+        game = game
+          .dimension_x(x.parse().map_err(|_| bad())?)
+          .dimension_y(y.parse().map_err(|_| bad())?);
+      }
+      _ => return Err(USAGE.to_owned()),
+    }
+  }
+  Ok((addr, ServerOptions::new(&game).tick_millis(tick_millis)))
+}
+
+fn main() {
+  let (addr, opts) = parse_args().unwrap_or_else(|e| {
+    eprintln!("{}", e);
+    process::exit(2);
+  });
+  let server = Server::bind(&addr, &opts).unwrap_or_else(|e| {
+    eprintln!("unable to listen on {}: {}", addr, e);
+    process::exit(1);
+  });
+  println!("listening on {}", addr);
+  if let Err(e) = server.run() {
+    eprintln!("{}", e);
+    process::exit(1);
+  }
+}
@@ -1,242 +1,773 @@
 use crate::err::{SnakeCtrlErr, SnakeCtrlResult};
+use crate::food::{self, Food, FoodKind};
 use crate::matrix::Matrix;
-use crate::options::InnerCfg;
+use crate::occupancy::Occupancy;
+use crate::options::{DeathRule, InnerCfg, SnakeStart};
+use crate::tick::{DeathCause, TickEvent, TickOutcome};
 // use crate::utils::simple_rand;
 use crate::{Direction, Point};
-use rand::Rng;
-use std::rc::Rc;
-
-pub struct Food {}
-
-impl Food {
-  fn generate(cfg: &InnerCfg, snake: &[Point], food: &[Point]) -> Point {
-    let max_x = cfg.dimension_x;
-    let max_y = cfg.dimension_y;
-
-    if (max_x * max_y - 1) <= (snake.len() + food.len()) as u16 {}
-
-    let mut rng = rand::rngs::ThreadRng::default();
-    let mut apple = snake[0];
-
-    let occupied_points = [snake, food].concat();
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Snake {
+  pub(crate) id: usize,
+  pub(crate) body: VecDeque<Point>,
+  pub(crate) current_direction: Direction,
+  pub(crate) next_direction: Direction,
+  pub(crate) score: u64,
+  /// Number of eaten apples, other kinds of food do not count.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) apples: u64,
+  /// Ticks left during which the tail stays in place.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) pending_growth: u32,
+  /// Turns for the next ticks, each one checked against the one before.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) queued: VecDeque<Direction>,
+  pub(crate) is_alive: bool,
+}
 
-    while occupied_points.iter().any(|p| p == &apple) {
-      let x = rng.gen_range(0, max_x);
-      let y = rng.gen_range(0, max_y);
-      apple = Point(x, y);
+impl Snake {
+  /// A snake placed as `start` says. A straight body has to fit in the
+  /// board, an explicit one has to be a chain of cells inside it that does
+  /// not turn back under the head.
+  fn create(
+    id: usize,
+    start: &SnakeStart,
+    cfg: &InnerCfg,
+  ) -> SnakeCtrlResult<Self> {
+    let direction = start.direction;
+    let body = if start.body.is_empty() {
+      Snake::straight_body(start.head, direction, cfg)?
+    } else {
+      let mut body = Vec::with_capacity(start.body.len() + 1);
+      body.push(start.head);
+      body.extend_from_slice(&start.body);
+      Snake::check_body(id, &body, direction, cfg)?;
+      body
     }
-
-    apple
+    .into();
+    Ok(Snake {
+      id,
+      body,
+      current_direction: direction,
+      next_direction: direction,
+      score: 0,
+      apples: 0,
+      pending_growth: 0,
+      queued: VecDeque::new(),
+      is_alive: true,
+    })
   }
 
-  fn clear_eaten(food: &mut Vec<Point>, eaten: &Point) {
-    if let Some(pos) = food.iter().position(|p| p == eaten) {
-      food.remove(pos);
+  /// Queues a turn. A full queue has its last turn replaced. A turn back
+  /// into the neck is ignored, unless `fail_on_revert` is set. The queue
+  /// holds at least one turn whatever the config says.
+  pub(crate) fn turn(&mut self, direction: Direction, cfg: &InnerCfg) {
+    let len = self.queued.len();
+    let kept = if len >= usize::from(cfg.input_queue_depth).max(1) {
+      len - 1
+    } else {
+      len
+    };
+    let prev = match kept {
+      0 => self.current_direction,
+      _ => self.queued[kept - 1],
+    };
+    if direction == prev
+      || (prev.opposite_direction() == direction && !cfg.fail_on_revert)
+    {
+      return;
     }
+    self.queued.truncate(kept);
+    self.queued.push_back(direction);
   }
-}
-
-pub struct Snake {}
-
-impl Snake {
-  fn create(center: Point, size: u16) -> SnakeCtrlResult<Vec<Point>> {
-    let Point(center_x, center_y) = center;
 
-    if center_x < size {
-      return Err(SnakeCtrlErr::InitSnakeSizeIsBig);
-    }
+  fn straight_body(
+    head: Point,
+    direction: Direction,
+    cfg: &InnerCfg,
+  ) -> SnakeCtrlResult<Vec<Point>> {
+    let (head_x, head_y) = (i32::from(head.0), i32::from(head.1));
+    let (step_x, step_y) = match direction {
+      Direction::Right => (-1, 0),
+      Direction::Left => (1, 0),
+      Direction::Top => (0, -1),
+      Direction::Bottom => (0, 1),
+    };
 
-    let mut snake = Vec::with_capacity(usize::from(size));
-    for snake_part_ind in 0..size {
-      snake.push(Point(center_x - snake_part_ind, center_y));
+    let size = cfg.initial_snake_size;
+    let mut body = Vec::with_capacity(usize::from(size));
+    for snake_part_ind in 0..i32::from(size) {
+      let x = head_x + step_x * snake_part_ind;
+      let y = head_y + step_y * snake_part_ind;
+      if x < 0
+        || y < 0
+        || x >= i32::from(cfg.dimension_x)
+        || y >= i32::from(cfg.dimension_y)
+      {
+        return Err(SnakeCtrlErr::InitSnakeSizeIsBig);
+      }
+      body.push(Point(x as u16, y as u16));
     }
-
-    Ok(snake)
+    Ok(body)
   }
 
-  fn move_snake(
-    cfg: &InnerCfg,
-    snake: &mut Vec<Point>,
+  fn check_body(
+    id: usize,
+    body: &[Point],
     direction: Direction,
-  ) -> SnakeCtrlResult<Point> {
-    let last = if let Some(l) = snake.pop() {
-      l
-    } else {
-      return Err(SnakeCtrlErr::SnakeIsZero);
-    };
+    cfg: &InnerCfg,
+  ) -> SnakeCtrlResult<()> {
+    for p in body {
+      if p.0 >= cfg.dimension_x {
+        return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(p.0));
+      }
+      if p.1 >= cfg.dimension_y {
+        return Err(SnakeCtrlErr::RowIndexOutOfBounds(p.1));
+      }
+    }
+    let is_chain = body.windows(2).all(|w| w[0].is_next_to(&w[1]));
+    let overlaps = body
+      .iter()
+      .enumerate()
+      .any(|(ind, p)| body[ind + 1..].contains(p));
+    let faces_neck = body[1].offset_from_near(&body[0]) == Some(direction);
+    if !is_chain || overlaps || faces_neck {
+      return Err(SnakeCtrlErr::BrokenSnake(id));
+    }
+    Ok(())
+  }
 
-    let head = if let Some(f) = snake.first() {
-      f
-    } else {
-      return Err(SnakeCtrlErr::SnakeIsZero);
-    };
+  pub(crate) fn head(&self) -> Point {
+    self.body[0]
+  }
 
-    let head_x = head.0;
-    let head_y = head.1;
+  /// Where the head goes next and whether it went through a wall or a
+  /// portal, or `None` when it hits the wall.
+  fn next_head(&self, cfg: &InnerCfg) -> Option<(Point, bool)> {
+    let Point(head_x, head_y) = self.head();
 
-    let new_head = match direction {
+    let new_head_unnormalized = match self.next_direction {
       Direction::Right => (head_x as i32 + 1, head_y as i32),
       Direction::Top => (head_x as i32, head_y as i32 + 1),
       Direction::Bottom => (head_x as i32, head_y as i32 - 1),
       Direction::Left => (head_x as i32 - 1, head_y as i32),
     };
 
-    let new_head = Snake::try_teleport_head_if_need(cfg, new_head)?;
-
-    snake.insert(0, new_head);
-
-    if Snake::is_ate_itself(snake) {
-      return Err(SnakeCtrlErr::SnakeAteItself);
-    }
-
-    Ok(last)
-  }
-
-  fn is_ate_itself(snake: &[Point]) -> bool {
-    let head = &snake[0];
-    for i in 1..snake.len() {
-      let snake_part = &snake[i];
-      if snake_part == head {
-        return true;
-      }
-    }
-    false
-  }
-
-  fn has_eaten(snake: &[Point], food: &[Point]) -> Option<Point> {
-    let first = &snake[0];
-    for f in food {
-      if f == first {
-        return Some(*f);
-      }
+    let new_head =
+      Snake::try_teleport_head_if_need(cfg, new_head_unnormalized)?;
+    if let Some(exit) = cfg.portal_exit(new_head) {
+      return Some((exit, true));
     }
-    None
+    let teleported =
+      (new_head.0 as i32, new_head.1 as i32) != new_head_unnormalized;
+    Some((new_head, teleported))
   }
 
   fn try_teleport_head_if_need(
     cfg: &InnerCfg,
     new_head_unnormalized: (i32, i32),
-  ) -> SnakeCtrlResult<Point> {
+  ) -> Option<Point> {
     let (new_head_x, new_head_y) = new_head_unnormalized;
-    let can_teleport = cfg.walking_through_the_walls;
-    let err =
-      || -> SnakeCtrlResult<Point> { Err(SnakeCtrlErr::SnakeHitTheWall) };
+    let wrap = cfg.wrap();
     if new_head_unnormalized.0 < 0 {
-      if !can_teleport {
-        return err();
+      if !wrap.left {
+        return None;
       }
-      return Ok(Point(cfg.dimension_x - 1, new_head_y as u16));
+      return Some(Point(cfg.dimension_x - 1, new_head_y as u16));
     } else if new_head_y < 0 {
-      if !can_teleport {
-        return err();
+      if !wrap.bottom {
+        return None;
       }
-      return Ok(Point(new_head_x as u16, cfg.dimension_y - 1));
+      return Some(Point(new_head_x as u16, cfg.dimension_y - 1));
     } else if new_head_x > (cfg.dimension_x as i32 - 1) {
-      if !can_teleport {
-        return err();
+      if !wrap.right {
+        return None;
       }
-      return Ok(Point(0, new_head_y as u16));
+      return Some(Point(0, new_head_y as u16));
     } else if new_head_unnormalized.1 > (cfg.dimension_y as i32 - 1) {
-      if !can_teleport {
-        return err();
+      if !wrap.top {
+        return None;
       }
-      return Ok(Point(new_head_x as u16, 0));
+      return Some(Point(new_head_x as u16, 0));
     }
 
-    Ok(Point(new_head_x as u16, new_head_y as u16))
+    Some(Point(new_head_x as u16, new_head_y as u16))
   }
 }
 
+/// What a snake is about to do during the current tick.
+struct Plan {
+  ind: usize,
+  from: Point,
+  to: Option<(Point, bool)>,
+  eats: Option<FoodKind>,
+  death: Option<(DeathCause, Point)>,
+}
+
+#[derive(Clone)]
 pub(crate) struct Board {
-  cfg: Rc<InnerCfg>,
+  cfg: Arc<InnerCfg>,
   dim_x: u16,
   dim_y: u16,
-
-  pub(crate) snake: Vec<Point>,
-  pub(crate) food: Vec<Point>,
+  seed: u64,
+  rng: Pcg32,
+  /// `cfg.walls` as a grid, indexed by `y * dim_x + x`. It never changes,
+  /// so clones share it like the config.
+  wall_cells: Arc<[bool]>,
+  wall_count: usize,
+  occupancy: Occupancy,
+
+  pub(crate) snakes: Vec<Snake>,
+  pub(crate) food: Vec<Food>,
 }
+
+/// Shrinking stops at this length.
+const MIN_SNAKE_LEN: usize = 2;
+
 impl Board {
-  pub(crate) fn new(cfg: Rc<InnerCfg>) -> SnakeCtrlResult<Self> {
+  pub(crate) fn new(cfg: Arc<InnerCfg>) -> SnakeCtrlResult<Self> {
     let InnerCfg {
       dimension_x,
       dimension_y,
-      initial_snake_size,
+      seed,
       ..
     } = *cfg;
 
-    let center = Board::center_of(dimension_x, dimension_y);
-    let snake = Snake::create(center, initial_snake_size)?;
+    let wall_cells = Board::wall_cells(&cfg)?;
+    Board::check_portals(&cfg, &wall_cells)?;
+    let snakes = Board::spawn_snakes(&cfg, &wall_cells)?;
 
     let mut board = Board {
       cfg,
       dim_x: dimension_x,
       dim_y: dimension_y,
-      snake,
-      food: Vec::with_capacity(1),
+      seed,
+      rng: Pcg32::seed_from_u64(seed),
+      wall_count: wall_cells.iter().filter(|w| **w).count(),
+      wall_cells,
+      occupancy: Occupancy::new(dimension_x, dimension_y),
+      snakes,
+      food: vec![],
     };
+    board.place_initial_food()?;
 
-    if board.cfg.auto_gen_food {
-      board.generate_food();
-    }
+    Ok(board)
+  }
 
+  /// A board from saved parts. No alive snake may be on a wall or on
+  /// another one, food may not be on a wall, an alive snake or other food.
+  #[cfg(feature = "serde")]
+  pub(crate) fn from_parts(
+    cfg: Arc<InnerCfg>,
+    seed: u64,
+    rng: Pcg32,
+    snakes: Vec<Snake>,
+    food: Vec<Food>,
+  ) -> SnakeCtrlResult<Self> {
+    let wall_cells = Board::wall_cells(&cfg)?;
+    Board::check_portals(&cfg, &wall_cells)?;
+    let mut board = Board {
+      dim_x: cfg.dimension_x,
+      dim_y: cfg.dimension_y,
+      wall_count: wall_cells.iter().filter(|w| **w).count(),
+      wall_cells,
+      occupancy: Occupancy::new(cfg.dimension_x, cfg.dimension_y),
+      cfg,
+      seed,
+      rng,
+      snakes,
+      food,
+    };
+    for (ind, snake) in board.snakes.iter().enumerate() {
+      for p in snake.body.iter().filter(|_| snake.is_alive) {
+        if board.is_wall(*p) || board.occupancy.owner(*p).is_some() {
+          return Err(SnakeCtrlErr::SnakeBlocked(ind));
+        }
+        board.occupancy.set_owner(*p, ind);
+      }
+    }
+    for f in &board.food {
+      if board.is_wall(f.point)
+        || board.occupancy.owner(f.point).is_some()
+        || board.occupancy.has_food(f.point)
+      {
+        return Err(SnakeCtrlErr::FoodBlocked(f.point));
+      }
+      board.occupancy.set_food(f.point, true);
+    }
     Ok(board)
   }
 
+  /// Builds the grid from scratch out of the snakes and the food.
+  fn fill_occupancy(&mut self) {
+    self.occupancy = Occupancy::new(self.dim_x, self.dim_y);
+    for (ind, snake) in self.snakes.iter().enumerate() {
+      if snake.is_alive {
+        for p in &snake.body {
+          self.occupancy.set_owner(*p, ind);
+        }
+      }
+    }
+    for f in &self.food {
+      self.occupancy.set_food(f.point, true);
+    }
+  }
+
+  fn wall_cells(cfg: &InnerCfg) -> SnakeCtrlResult<Arc<[bool]>> {
+    let dim_x = usize::from(cfg.dimension_x);
+    let mut cells = vec![false; dim_x * usize::from(cfg.dimension_y)];
+    for p in &cfg.walls {
+      if p.0 >= cfg.dimension_x {
+        return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(p.0));
+      }
+      if p.1 >= cfg.dimension_y {
+        return Err(SnakeCtrlErr::RowIndexOutOfBounds(p.1));
+      }
+      cells[usize::from(p.1) * dim_x + usize::from(p.0)] = true;
+    }
+    Ok(cells.into())
+  }
+
+  /// Portal cells must be on the board, off the walls and in one pair
+  /// only.
+  fn check_portals(cfg: &InnerCfg, wall_cells: &[bool]) -> SnakeCtrlResult<()> {
+    let dim_x = usize::from(cfg.dimension_x);
+    let mut seen = vec![];
+    for p in cfg.portals.iter().flat_map(|(a, b)| [*a, *b]) {
+      if p.0 >= cfg.dimension_x {
+        return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(p.0));
+      }
+      if p.1 >= cfg.dimension_y {
+        return Err(SnakeCtrlErr::RowIndexOutOfBounds(p.1));
+      }
+      let on_wall = wall_cells[usize::from(p.1) * dim_x + usize::from(p.0)];
+      if on_wall || seen.contains(&p) {
+        return Err(SnakeCtrlErr::BadPortal(p));
+      }
+      seen.push(p);
+    }
+    Ok(())
+  }
+
+  fn is_wall(&self, p: Point) -> bool {
+    self.wall_cells
+      [usize::from(p.1) * usize::from(self.dim_x) + usize::from(p.0)]
+  }
+
+  fn is_portal(&self, p: Point) -> bool {
+    self.cfg.portals.iter().any(|(a, b)| *a == p || *b == p)
+  }
+
+  #[cfg(feature = "serde")]
+  pub(crate) fn rng(&self) -> &Pcg32 {
+    &self.rng
+  }
+
+  /// Snakes start where `cfg.starts` says, the rest are placed
+  /// automatically. No snake may start on a wall, a portal or another
+  /// snake.
+  fn spawn_snakes(
+    cfg: &InnerCfg,
+    wall_cells: &[bool],
+  ) -> SnakeCtrlResult<Vec<Snake>> {
+    let players = u16::from(cfg.players);
+    if players > cfg.dimension_y {
+      return Err(SnakeCtrlErr::TooManyPlayers);
+    }
+
+    let snakes = (0..players)
+      .map(|id| Snake::create(usize::from(id), &cfg.start_of(id), cfg))
+      .collect::<SnakeCtrlResult<Vec<Snake>>>()?;
+
+    let dim_x = usize::from(cfg.dimension_x);
+    let mut taken = wall_cells.to_vec();
+    for (a, b) in &cfg.portals {
+      for p in &[a, b] {
+        taken[usize::from(p.1) * dim_x + usize::from(p.0)] = true;
+      }
+    }
+    for p in snakes.iter().flat_map(|s| s.body.iter()) {
+      let cell = &mut taken[usize::from(p.1) * dim_x + usize::from(p.0)];
+      if *cell {
+        return Err(SnakeCtrlErr::SpawnBlocked);
+      }
+      *cell = true;
+    }
+    Ok(snakes)
+  }
+
+  /// Restarts with the next seed, so a sequence of games stays
+  /// reproducible from the initial one.
   pub fn restart(&mut self) -> SnakeCtrlResult<()> {
-    self.snake = Snake::create(
-      Board::center_of(self.cfg.dimension_x, self.cfg.dimension_y),
-      self.cfg.initial_snake_size,
-    )?;
-    self.food = Vec::with_capacity(1);
-    if self.cfg.auto_gen_food {
+    self.restart_with_seed(self.seed.wrapping_add(1))
+  }
+
+  pub fn restart_with_seed(&mut self, seed: u64) -> SnakeCtrlResult<()> {
+    self.seed = seed;
+    self.rng = Pcg32::seed_from_u64(seed);
+    self.snakes = Board::spawn_snakes(&self.cfg, &self.wall_cells)?;
+    self.place_initial_food()
+  }
+
+  /// Puts the fixed food from the config, or random food when there is
+  /// none. Fixed food must be on the board and off the walls, the portals,
+  /// the snakes and other food.
+  fn place_initial_food(&mut self) -> SnakeCtrlResult<()> {
+    self.food.clear();
+    self.fill_occupancy();
+    let cfg = self.cfg.clone();
+    for p in &cfg.food {
+      if p.0 >= self.dim_x {
+        return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(p.0));
+      }
+      if p.1 >= self.dim_y {
+        return Err(SnakeCtrlErr::RowIndexOutOfBounds(p.1));
+      }
+      if self.is_wall(*p)
+        || self.is_portal(*p)
+        || self.occupancy.owner(*p).is_some()
+        || self.occupancy.has_food(*p)
+      {
+        return Err(SnakeCtrlErr::FoodBlocked(*p));
+      }
+      self.occupancy.set_food(*p, true);
+    }
+    self.food = cfg.food.iter().copied().map(Food::apple).collect();
+    if self.food.is_empty() && self.cfg.auto_gen_food {
       self.generate_food();
     }
     Ok(())
   }
 
-  pub(crate) fn move_snake(
-    &mut self,
-    direction: Direction,
-  ) -> SnakeCtrlResult<bool> {
-    let removed_last =
-      Snake::move_snake(&self.cfg, &mut self.snake, direction)?;
+  pub(crate) fn snake(&self, id: usize) -> SnakeCtrlResult<&Snake> {
+    self.snakes.get(id).ok_or(SnakeCtrlErr::UnknownPlayer(id))
+  }
+
+  pub(crate) fn snake_mut(&mut self, id: usize) -> SnakeCtrlResult<&mut Snake> {
+    self
+      .snakes
+      .get_mut(id)
+      .ok_or(SnakeCtrlErr::UnknownPlayer(id))
+  }
+
+  fn alive_snakes(&self) -> impl Iterator<Item = &Snake> {
+    self.snakes.iter().filter(|s| s.is_alive)
+  }
+
+  /// Snakes to show: the alive ones, and the dead ones as well when the
+  /// game ends on the first death.
+  pub(crate) fn visible_snakes(&self) -> impl Iterator<Item = &Snake> {
+    let keep_dead = self.cfg.death_rule == DeathRule::EndGame;
+    self.snakes.iter().filter(move |s| s.is_alive || keep_dead)
+  }
+
+  /// Moves every alive snake one cell at the same time.
+  pub(crate) fn move_snakes(&mut self) -> SnakeCtrlResult<TickOutcome> {
+    let mut outcome = TickOutcome::default();
+
+    for snake in self.snakes.iter_mut().filter(|s| s.is_alive) {
+      if let Some(direction) = snake.queued.pop_front() {
+        snake.next_direction = direction;
+      }
+    }
+
+    let mut plans = Vec::with_capacity(self.snakes.len());
+    for (ind, snake) in self.snakes.iter().enumerate() {
+      if !snake.is_alive {
+        continue;
+      }
+      if snake.body.is_empty() {
+        return Err(SnakeCtrlErr::SnakeIsZero);
+      }
+      let to = snake.next_head(&self.cfg);
+      plans.push(Plan {
+        ind,
+        from: snake.head(),
+        to,
+        eats: to.and_then(|(p, _)| self.food_at(p)),
+        death: None,
+      });
+    }
+
+    self.find_collisions(&mut plans);
+
+    let mut eaten_count = 0;
+    for plan in &plans {
+      let snake = &mut self.snakes[plan.ind];
+      let (to, teleported) = match (plan.death, plan.to) {
+        (None, Some(to)) => to,
+        _ => continue,
+      };
+
+      let old_len = snake.body.len();
+      let growth = plan.eats.map_or(0, |kind| kind.growth());
+      if growth > 0 {
+        let per_food = u32::from(self.cfg.growth_per_food);
+        snake.pending_growth += growth as u32 * per_food;
+      } else if growth < 0 {
+        snake.pending_growth = 0;
+      }
+      let new_len = if snake.pending_growth > 0 {
+        snake.pending_growth -= 1;
+        old_len + 1
+      } else {
+        let min_len = MIN_SNAKE_LEN.min(old_len) as i32;
+        (old_len as i32 + growth.min(0)).max(min_len) as usize
+      };
+      snake.body.push_front(to);
+      while snake.body.len() > new_len {
+        if let Some(tail) = snake.body.pop_back() {
+          self.occupancy.release(tail, plan.ind);
+        }
+      }
+      self.occupancy.set_owner(to, plan.ind);
+      snake.current_direction = snake.next_direction;
+
+      let id = snake.id;
+      outcome.events.push(TickEvent::Moved {
+        snake: id,
+        from: plan.from,
+        to,
+      });
+      if teleported {
+        outcome.events.push(TickEvent::Teleported {
+          snake: id,
+          from: plan.from,
+          to,
+        });
+      }
 
-    let eaten = Snake::has_eaten(&self.snake, &self.food);
+      if let Some(kind) = plan.eats {
+        snake.score += kind.score();
+        if kind == FoodKind::Apple {
+          snake.apples += 1;
+        }
+        self.food.retain(|f| f.point != to);
+        self.occupancy.set_food(to, false);
+        eaten_count += 1;
+        outcome.events.push(TickEvent::Ate {
+          snake: id,
+          at: to,
+          kind,
+        });
+      }
+      if new_len > old_len {
+        outcome.events.push(TickEvent::Grew {
+          snake: id,
+          length: new_len,
+        });
+      } else if new_len < old_len {
+        outcome.events.push(TickEvent::Shrank {
+          snake: id,
+          length: new_len,
+        });
+      }
+    }
 
-    if let Some(e) = eaten {
-      self.snake.push(removed_last);
-      Food::clear_eaten(&mut self.food, &e);
+    let mut someone_died = false;
+    for plan in &plans {
+      if let Some((cause, at)) = plan.death {
+        someone_died = true;
+        self.kill(plan.ind);
+        let snake = self.snakes[plan.ind].id;
+        outcome.events.push(TickEvent::Died { snake, cause, at });
+      }
+    }
 
-      if self.cfg.auto_gen_food {
-        self.generate_food();
+    let expired = self.expire_food();
+    for p in &expired {
+      outcome.events.push(TickEvent::FoodExpired(*p));
+    }
+
+    let is_full = self.is_full();
+    if self.cfg.auto_gen_food && !is_full {
+      for _ in 0..eaten_count + expired.len() {
+        if let Some(spawned) = self.generate_food() {
+          outcome.events.push(TickEvent::FoodSpawned(spawned));
+        }
       }
-      return Ok(true);
     }
-    return Ok(false);
+
+    let alive: Vec<&Snake> = self.alive_snakes().collect();
+    let is_over = if self.snakes.len() == 1 {
+      alive.is_empty() || is_full
+    } else {
+      alive.len() <= 1
+        || is_full
+        || (someone_died && self.cfg.death_rule == DeathRule::EndGame)
+    };
+    if is_over {
+      let winner = if self.snakes.len() > 1 && alive.len() == 1 {
+        Some(alive[0].id)
+      } else if is_full {
+        alive.iter().max_by_key(|s| s.body.len()).map(|s| s.id)
+      } else {
+        None
+      };
+      if let Some(w) = winner {
+        outcome.events.push(TickEvent::Won(w));
+      }
+      outcome.events.push(TickEvent::GameOver);
+    }
+
+    Ok(outcome)
   }
 
-  pub(crate) fn generate_food(&mut self) {
-    self
-      .food
-      .push(Food::generate(&self.cfg, &self.snake, &self.food));
+  /// Fills in `Plan::death`. A head may take a cell that a tail leaves
+  /// during the same tick, two heads meeting in one cell both die.
+  fn find_collisions(&self, plans: &mut [Plan]) {
+    let mut tail_moves = vec![false; self.snakes.len()];
+    for plan in plans.iter() {
+      let snake = &self.snakes[plan.ind];
+      let grows = snake.pending_growth > 0
+        || (self.cfg.growth_per_food > 0
+          && plan.eats.is_some_and(|kind| kind.growth() > 0));
+      tail_moves[plan.ind] = plan.to.is_some() && !grows;
+    }
+
+    for i in 0..plans.len() {
+      let (to, _) = match plans[i].to {
+        Some(to) => to,
+        None => {
+          plans[i].death = Some((DeathCause::HitTheWall, plans[i].from));
+          continue;
+        }
+      };
+
+      let meets_head = plans
+        .iter()
+        .enumerate()
+        .any(|(j, other)| j != i && other.to.map(|(p, _)| p) == Some(to));
+      let owner = self.occupancy.owner(to).filter(|owner| {
+        let leaves = self.snakes[*owner].body.back() == Some(&to);
+        !(leaves && tail_moves[*owner])
+      });
+      let death = if self.is_wall(to) {
+        Some(DeathCause::HitObstacle)
+      } else if meets_head {
+        Some(DeathCause::HeadToHead)
+      } else {
+        match owner {
+          Some(owner) if owner == plans[i].ind => Some(DeathCause::AteItself),
+          Some(_) => Some(DeathCause::HitSnake),
+          None if plans[i].eats.is_some_and(|k| k.is_deadly()) => {
+            Some(DeathCause::Poisoned)
+          }
+          None => None,
+        }
+      };
+      plans[i].death = death.map(|cause| (cause, to));
+    }
   }
 
-  fn center_of(dim_x: u16, dim_y: u16) -> Point {
-    let center_x = dim_x / 2;
-    let center_y = dim_y / 2;
-    Point(center_x, center_y)
+  fn kill(&mut self, ind: usize) {
+    let snake = &mut self.snakes[ind];
+    snake.is_alive = false;
+    for p in &snake.body {
+      self.occupancy.release(*p, ind);
+    }
+    if self.cfg.death_rule == DeathRule::TurnIntoFood {
+      for p in snake.body.clone() {
+        if self.occupancy.owner(p).is_none() && !self.occupancy.has_food(p) {
+          self.food.push(Food::apple(p));
+          self.occupancy.set_food(p, true);
+        }
+      }
+    }
+  }
+
+  fn food_at(&self, p: Point) -> Option<FoodKind> {
+    if !self.occupancy.has_food(p) {
+      return None;
+    }
+    self.food.iter().find(|f| f.point == p).map(|f| f.kind)
+  }
+
+  /// Counts down the food lifetimes and removes the expired food.
+  fn expire_food(&mut self) -> Vec<Point> {
+    let mut expired = vec![];
+    let occupancy = &mut self.occupancy;
+    self.food.retain_mut(|f| match &mut f.ticks_left {
+      Some(ticks) if *ticks <= 1 => {
+        expired.push(f.point);
+        occupancy.set_food(f.point, false);
+        false
+      }
+      Some(ticks) => {
+        *ticks -= 1;
+        true
+      }
+      None => true,
+    });
+    expired
+  }
+
+  pub(crate) fn generate_food(&mut self) -> Option<Point> {
+    let (occupancy, wall_cells) = (&self.occupancy, &self.wall_cells);
+    let portals = &self.cfg.portals;
+    let is_free = |ind: usize, p: Point| {
+      occupancy.is_free(ind)
+        && !wall_cells[ind]
+        && !portals.iter().any(|(a, b)| *a == p || *b == p)
+    };
+    let point = food::free_cell(&self.cfg, &mut self.rng, is_free)?;
+    self.occupancy.set_food(point, true);
+    let spawn = food::pick_spawn(&self.cfg, &mut self.rng);
+    self.food.push(Food {
+      point,
+      kind: spawn.kind,
+      ticks_left: spawn.lifetime,
+    });
+    Some(point)
+  }
+
+  /// Whether the snakes cover every cell food could take, that is every
+  /// cell but the walls and the portals like in `generate_food`.
+  pub(crate) fn is_full(&self) -> bool {
+    let (mut portal_cells, mut covered_portals) = (0, 0);
+    for p in self.cfg.portals.iter().flat_map(|(a, b)| [*a, *b]) {
+      portal_cells += 1;
+      if self.occupancy.owner(p).is_some() {
+        covered_portals += 1;
+      }
+    }
+    let taken: usize = self.alive_snakes().map(|s| s.body.len()).sum();
+    let cells = usize::from(self.dim_x) * usize::from(self.dim_y);
+    taken - covered_portals >= cells - self.wall_count - portal_cells
+  }
+
+  pub(crate) fn seed(&self) -> u64 {
+    self.seed
   }
 
   pub(crate) fn clone_snake(&self) -> Vec<Point> {
-    self.snake.clone()
+    self.snakes[0].body.iter().copied().collect()
   }
   pub(crate) fn clone_food(&self) -> Vec<Point> {
-    self.food.clone()
+    self.food.iter().map(|f| f.point).collect()
+  }
+
+  /// Body cells of the alive snakes other than `id`.
+  pub(crate) fn others_of(&self, id: usize) -> Vec<Point> {
+    self
+      .alive_snakes()
+      .filter(|s| s.id != id)
+      .flat_map(|s| s.body.iter().copied())
+      .collect()
   }
 
   pub(crate) fn get_matrix(&self) -> Matrix {
     let mut m = Matrix::new(self.dim_x, self.dim_y);
-    m.add_snake(&self.snake);
+    for snake in self.visible_snakes() {
+      m.add_snake(snake.body.iter());
+    }
+    m.add_walls(&self.cfg.walls);
     m.add_food(&self.food);
     m
   }
@@ -0,0 +1,254 @@
+//! Gym-style training environment over the first player of a game.
+//!
+//! `Env::reset` starts an episode and `Env::step` plays one relative
+//! action, both return the observation built by the `Encoder`.
+
+use crate::ai::{Grid, DIRECTIONS};
+use crate::{
+  Cell, DeathCause, Direction, SnakeCtrl, SnakeCtrlErr, SnakeCtrlOptions,
+  SnakeCtrlResult,
+};
+
+/// A turn relative to where the head looks.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Action {
+  Left,
+  Straight,
+  Right,
+}
+
+impl Action {
+  pub const ALL: [Action; 3] = [Action::Left, Action::Straight, Action::Right];
+
+  /// The direction the snake takes when looking at `current`.
+  pub fn direction_from(self, current: Direction) -> Direction {
+    let ind = DIRECTIONS.iter().position(|d| *d == current).unwrap();
+    match self {
+      Action::Left => DIRECTIONS[(ind + 3) % 4],
+      Action::Straight => current,
+      Action::Right => DIRECTIONS[(ind + 1) % 4],
+    }
+  }
+}
+
+/// Turns the game into the values a model is fed with.
+pub trait Encoder: Send + Sync {
+  /// Number of values `encode` returns for the game.
+  fn size(&self, ctrl: &SnakeCtrl) -> usize;
+
+  fn encode(&self, ctrl: &SnakeCtrl) -> Vec<f32>;
+}
+
+/// `Matrix::one_hot` of the board, `Cell::COUNT` planes of
+/// `dimension_x * dimension_y` values.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GridPlanes;
+
+impl Encoder for GridPlanes {
+  fn size(&self, ctrl: &SnakeCtrl) -> usize {
+    let (dim_x, dim_y) = ctrl.dimensions();
+    Cell::COUNT * usize::from(dim_x) * usize::from(dim_y)
+  }
+
+  fn encode(&self, ctrl: &SnakeCtrl) -> Vec<f32> {
+    ctrl.get_matrix().one_hot()
+  }
+}
+
+/// Eleven flags, 1.0 or 0.0: danger straight, right and left of the head,
+/// the direction of the head (top, right, bottom, left) and where the
+/// nearest food is (above, right, below, left of the head).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Features;
+
+impl Features {
+  pub const SIZE: usize = 11;
+}
+
+impl Encoder for Features {
+  fn size(&self, _ctrl: &SnakeCtrl) -> usize {
+    Features::SIZE
+  }
+
+  fn encode(&self, ctrl: &SnakeCtrl) -> Vec<f32> {
+    let obs = ctrl.observe();
+    let grid = Grid::from_obs(&obs);
+    let blocked = grid.blocked_by(&obs.snake, &obs.obstacles());
+    let head = obs.head();
+    let danger = |action: Action| {
+      let direction = action.direction_from(obs.direction);
+      grid
+        .step(head, direction)
+        .map_or(true, |p| blocked[grid.index(p)])
+    };
+    let food = obs.food.iter().min_by_key(|f| grid.distance(head, **f));
+
+    let mut flags = vec![
+      danger(Action::Straight),
+      danger(Action::Right),
+      danger(Action::Left),
+    ];
+    flags.extend(DIRECTIONS.iter().map(|d| *d == obs.direction));
+    flags.extend_from_slice(&match food {
+      Some(f) => [f.1 > head.1, f.0 > head.0, f.1 < head.1, f.0 < head.0],
+      None => [false; 4],
+    });
+    flags
+      .into_iter()
+      .map(|f| if f { 1.0 } else { 0.0 })
+      .collect()
+  }
+}
+
+/// Reward shaping of `Env::step`, the rewards of a step are summed up.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rewards {
+  /// Per point of score the eaten food gives.
+  pub food: f32,
+  pub death: f32,
+  /// Every step, a small negative value hurries the snake.
+  pub step: f32,
+  pub win: f32,
+  /// Given when the head gets closer to the nearest food and taken when it
+  /// gets further.
+  pub approach: f32,
+}
+
+impl Default for Rewards {
+  fn default() -> Self {
+    Rewards {
+      food: 1.0,
+      death: -1.0,
+      step: 0.0,
+      win: 1.0,
+      approach: 0.0,
+    }
+  }
+}
+
+/// What happened during a step besides the reward.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepInfo {
+  pub score: u64,
+  pub length: usize,
+  pub ticks: u64,
+  pub ate: bool,
+  pub death: Option<DeathCause>,
+  pub is_won: bool,
+  /// The episode was cut by `Env::max_ticks`, not ended by the game.
+  pub truncated: bool,
+}
+
+pub struct Env {
+  ctrl: SnakeCtrl,
+  encoder: Box<dyn Encoder>,
+  rewards: Rewards,
+  max_ticks: Option<u64>,
+  is_done: bool,
+}
+
+impl Env {
+  /// An environment with the `Features` encoder and the default rewards.
+  pub fn new(opts: &SnakeCtrlOptions) -> SnakeCtrlResult<Self> {
+    Ok(Env {
+      ctrl: SnakeCtrl::new(opts)?,
+      encoder: Box::new(Features),
+      rewards: Rewards::default(),
+      max_ticks: None,
+      is_done: false,
+    })
+  }
+
+  pub fn encoder(mut self, val: Box<dyn Encoder>) -> Self {
+    self.encoder = val;
+    self
+  }
+
+  pub fn rewards(mut self, val: Rewards) -> Self {
+    self.rewards = val;
+    self
+  }
+
+  /// Ends the episode as truncated after this many ticks.
+  pub fn max_ticks(mut self, val: u64) -> Self {
+    self.max_ticks = Some(val);
+    self
+  }
+
+  pub fn game(&self) -> &SnakeCtrl {
+    &self.ctrl
+  }
+
+  /// Number of values in every observation.
+  pub fn observation_size(&self) -> usize {
+    self.encoder.size(&self.ctrl)
+  }
+
+  pub fn observe(&self) -> Vec<f32> {
+    self.encoder.encode(&self.ctrl)
+  }
+
+  /// Starts a new episode on the given seed.
+  pub fn reset(&mut self, seed: u64) -> SnakeCtrlResult<Vec<f32>> {
+    self.ctrl.restart_with_seed(seed)?;
+    self.is_done = false;
+    Ok(self.observe())
+  }
+
+  /// Plays one tick. Fails with `GameIsOver` once the episode is done
+  /// until the next `reset`.
+  pub fn step(
+    &mut self,
+    action: Action,
+  ) -> SnakeCtrlResult<(Vec<f32>, f32, bool, StepInfo)> {
+    if self.is_done {
+      return Err(SnakeCtrlErr::GameIsOver);
+    }
+    let score = self.ctrl.board.snakes[0].score;
+    let distance = self.food_distance();
+    let direction = action.direction_from(self.ctrl.current_direction());
+    self.ctrl.direction_to(direction)?;
+    let outcome = self.ctrl.next_tick()?;
+
+    let snake = &self.ctrl.board.snakes[0];
+    let mut info = StepInfo {
+      score: snake.score,
+      length: snake.body.len(),
+      ticks: self.ctrl.ticks(),
+      ate: outcome.eaten_by(0).is_some(),
+      death: outcome.death_of(0).map(|(cause, _)| cause),
+      is_won: outcome.winner() == Some(0),
+      truncated: false,
+    };
+    let mut reward = self.rewards.step
+      + self.rewards.food * info.score.saturating_sub(score) as f32;
+    if info.death.is_some() {
+      reward += self.rewards.death;
+    }
+    if info.is_won {
+      reward += self.rewards.win;
+    }
+    if !info.ate && info.death.is_none() {
+      if let (Some(before), Some(after)) = (distance, self.food_distance()) {
+        if after < before {
+          reward += self.rewards.approach;
+        } else if after > before {
+          reward -= self.rewards.approach;
+        }
+      }
+    }
+
+    info.truncated = !self.ctrl.is_over()
+      && self.max_ticks.is_some_and(|max| info.ticks >= max);
+    self.is_done = self.ctrl.is_over() || info.truncated;
+    Ok((self.observe(), reward, self.is_done, info))
+  }
+
+  fn food_distance(&self) -> Option<u32> {
+    let obs = self.ctrl.observe();
+    let grid = Grid::from_obs(&obs);
+    obs.food.iter().map(|f| grid.distance(obs.head(), *f)).min()
+  }
+}
@@ -1,3 +1,4 @@
+use crate::Point;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,14 +7,28 @@ pub enum SnakeCtrlErr {
   RowIndexOutOfBounds(u16),
   #[error("column index ({0}) is out of bounds")]
   ColumnIndexOutOfBounds(u16),
-  #[error("the snake ate itself")]
-  SnakeAteItself,
-  #[error("the snake hit the wall")]
-  SnakeHitTheWall,
   #[error("initial snake size is more than possible")]
   InitSnakeSizeIsBig,
   #[error("something is really wrong. your snake size is zero")]
   SnakeIsZero,
+  #[error("the game is over, restart it first")]
+  GameIsOver,
+  #[error("the snapshot has version {found}, {expected} is expected")]
+  SnapshotVersion { found: u32, expected: u32 },
+  #[error("there is no player with id {0}")]
+  UnknownPlayer(usize),
+  #[error("the board is too small for that many players")]
+  TooManyPlayers,
+  #[error("snake {0} is on a wall or on another snake")]
+  SnakeBlocked(usize),
+  #[error("the snake start overlaps a wall, a portal or another snake")]
+  SpawnBlocked,
+  #[error("the food cell {0:?} is on a wall, a portal, a snake or other food")]
+  FoodBlocked(Point),
+  #[error("the portal cell {0:?} is on a wall or in another portal")]
+  BadPortal(Point),
+  #[error("the body of snake {0} is not a chain of cells behind its head")]
+  BrokenSnake(usize),
 }
 
 pub type SnakeCtrlResult<T> = Result<T, SnakeCtrlErr>;
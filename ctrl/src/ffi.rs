@@ -0,0 +1,986 @@
+//! C ABI over `SnakeCtrl`.
+//!
+//! Every function takes an opaque `SnakeCtrl` handle created by
+//! `snake_ctrl_new` and returns a `SnakeCtrlCode`. Buffers handed out by the
+//! library are owned by it and must be released with the matching `*_free`
+//! function. The header is generated by `build.rs` into
+//! `include/snake_ctrl.h`.
+//!
+//! The `snake_ctrl_env_*` functions wrap `env::Env` the same way for
+//! training code that loads the shared library.
+
+use crate::env::{Action, Env, Features, GridPlanes, Rewards, StepInfo};
+use crate::options::InnerCfg;
+use crate::{
+  DeathCause, DeathRule, Direction, FoodKind, Point, SnakeCornerVariant,
+  SnakeCtrl, SnakeCtrlErr, SnakeCtrlOptions, SnakePartVariant, TickOutcome,
+};
+use std::convert::TryFrom;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnakeCtrlCode {
+  Ok = 0,
+  RowIndexOutOfBounds = 1,
+  ColumnIndexOutOfBounds = 2,
+  InitSnakeSizeIsBig = 3,
+  SnakeIsZero = 4,
+  GameIsOver = 5,
+  SnapshotVersion = 6,
+  UnknownPlayer = 7,
+  TooManyPlayers = 8,
+  SnakeBlocked = 9,
+  SpawnBlocked = 10,
+  FoodBlocked = 11,
+  BadPortal = 12,
+  BrokenSnake = 13,
+  NullPointer = 100,
+  BufferTooSmall = 101,
+  Panic = 102,
+  /// An enum argument is not one of its values.
+  InvalidValue = 103,
+}
+
+impl SnakeCtrlCode {
+  const ALL: [SnakeCtrlCode; 18] = [
+    SnakeCtrlCode::Ok,
+    SnakeCtrlCode::RowIndexOutOfBounds,
+    SnakeCtrlCode::ColumnIndexOutOfBounds,
+    SnakeCtrlCode::InitSnakeSizeIsBig,
+    SnakeCtrlCode::SnakeIsZero,
+    SnakeCtrlCode::GameIsOver,
+    SnakeCtrlCode::SnapshotVersion,
+    SnakeCtrlCode::UnknownPlayer,
+    SnakeCtrlCode::TooManyPlayers,
+    SnakeCtrlCode::SnakeBlocked,
+    SnakeCtrlCode::SpawnBlocked,
+    SnakeCtrlCode::FoodBlocked,
+    SnakeCtrlCode::BadPortal,
+    SnakeCtrlCode::BrokenSnake,
+    SnakeCtrlCode::NullPointer,
+    SnakeCtrlCode::BufferTooSmall,
+    SnakeCtrlCode::Panic,
+    SnakeCtrlCode::InvalidValue,
+  ];
+}
+
+impl From<&SnakeCtrlErr> for SnakeCtrlCode {
+  fn from(e: &SnakeCtrlErr) -> Self {
+    match e {
+      SnakeCtrlErr::RowIndexOutOfBounds(_) => {
+        SnakeCtrlCode::RowIndexOutOfBounds
+      }
+      SnakeCtrlErr::ColumnIndexOutOfBounds(_) => {
+        SnakeCtrlCode::ColumnIndexOutOfBounds
+      }
+      SnakeCtrlErr::InitSnakeSizeIsBig => SnakeCtrlCode::InitSnakeSizeIsBig,
+      SnakeCtrlErr::SnakeIsZero => SnakeCtrlCode::SnakeIsZero,
+      SnakeCtrlErr::GameIsOver => SnakeCtrlCode::GameIsOver,
+      SnakeCtrlErr::SnapshotVersion { .. } => SnakeCtrlCode::SnapshotVersion,
+      SnakeCtrlErr::UnknownPlayer(_) => SnakeCtrlCode::UnknownPlayer,
+      SnakeCtrlErr::TooManyPlayers => SnakeCtrlCode::TooManyPlayers,
+      SnakeCtrlErr::SnakeBlocked(_) => SnakeCtrlCode::SnakeBlocked,
+      SnakeCtrlErr::SpawnBlocked => SnakeCtrlCode::SpawnBlocked,
+      SnakeCtrlErr::FoodBlocked(_) => SnakeCtrlCode::FoodBlocked,
+      SnakeCtrlErr::BadPortal(_) => SnakeCtrlCode::BadPortal,
+      SnakeCtrlErr::BrokenSnake(_) => SnakeCtrlCode::BrokenSnake,
+    }
+  }
+}
+
+/// Enums come from C as plain integers, any value is possible there.
+macro_rules! enum_from_c {
+  ($ty:ty, [$($variant:expr),+ $(,)?]) => {
+    impl TryFrom<u32> for $ty {
+      type Error = SnakeCtrlCode;
+
+      fn try_from(val: u32) -> Result<Self, Self::Error> {
+        [$($variant),+]
+          .get(val as usize)
+          .copied()
+          .ok_or(SnakeCtrlCode::InvalidValue)
+      }
+    }
+  };
+}
+
+enum_from_c!(
+  Direction,
+  [
+    Direction::Top,
+    Direction::Right,
+    Direction::Bottom,
+    Direction::Left
+  ]
+);
+enum_from_c!(
+  DeathRule,
+  [
+    DeathRule::EndGame,
+    DeathRule::Remove,
+    DeathRule::TurnIntoFood
+  ]
+);
+enum_from_c!(Action, [Action::Left, Action::Straight, Action::Right]);
+enum_from_c!(
+  SnakeCtrlEnvEncoder,
+  [
+    SnakeCtrlEnvEncoder::Features,
+    SnakeCtrlEnvEncoder::GridPlanes
+  ]
+);
+
+#[repr(C)]
+pub struct SnakeCtrlCOptions {
+  pub dimension_x: u16,
+  pub dimension_y: u16,
+  pub initial_snake_size: u16,
+  pub walking_through_the_walls: bool,
+  pub fail_on_revert: bool,
+  pub auto_gen_food: bool,
+  /// When false a random seed is picked and `seed` is ignored.
+  pub use_seed: bool,
+  pub seed: u64,
+  pub players: u8,
+  /// A `DeathRule`.
+  pub death_rule: u32,
+  /// Obstacle cells, `walls_len` points. May be null when `walls_len` is 0.
+  pub walls: *const Point,
+  pub walls_len: usize,
+  /// Cells a snake gets for one food.
+  pub growth_per_food: u16,
+  /// Turns kept for the next ticks.
+  pub input_queue_depth: u8,
+}
+
+impl SnakeCtrlCOptions {
+  /// # Safety
+  /// `walls` must be valid for `walls_len` points.
+  unsafe fn to_options(&self) -> Result<SnakeCtrlOptions, SnakeCtrlCode> {
+    let walls = if self.walls.is_null() || self.walls_len == 0 {
+      vec![]
+    } else {
+      std::slice::from_raw_parts(self.walls, self.walls_len).to_vec()
+    };
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(self.dimension_x)
+      .dimension_y(self.dimension_y)
+      .initial_snake_size(self.initial_snake_size)
+      .walking_through_the_walls(self.walking_through_the_walls)
+      .fail_on_revert(self.fail_on_revert)
+      .auto_gen_food(self.auto_gen_food)
+      .players(self.players)
+      .death_rule(DeathRule::try_from(self.death_rule)?)
+      .walls(walls)
+      .growth_per_food(self.growth_per_food)
+      .input_queue_depth(self.input_queue_depth);
+    if self.use_seed {
+      Ok(opts.seed(self.seed))
+    } else {
+      Ok(opts)
+    }
+  }
+}
+
+/// Summary of one tick for the first player. The points are meaningful
+/// only when the matching flag is set.
+#[repr(C)]
+pub struct SnakeCtrlCTick {
+  pub head: Point,
+  pub length: usize,
+  pub is_teleported: bool,
+  pub is_ate: bool,
+  pub eaten: Point,
+  pub eaten_kind: FoodKind,
+  pub is_food_spawned: bool,
+  pub spawned: Point,
+  pub is_dead: bool,
+  pub death_cause: DeathCause,
+  pub death_point: Point,
+  pub is_won: bool,
+}
+
+impl SnakeCtrlCTick {
+  fn new(ctrl: &SnakeCtrl, outcome: &TickOutcome) -> Self {
+    let snake = &ctrl.board.snakes[0].body;
+    let (is_dead, death_cause, death_point) = match outcome.death_of(0) {
+      Some((cause, at)) => (true, cause, at),
+      None => (false, DeathCause::AteItself, Point(0, 0)),
+    };
+    SnakeCtrlCTick {
+      head: snake[0],
+      length: snake.len(),
+      is_teleported: outcome.is_teleported(),
+      is_ate: outcome.eaten_by(0).is_some(),
+      eaten: outcome.eaten_by(0).unwrap_or(Point(0, 0)),
+      eaten_kind: outcome.eaten_kind_by(0).unwrap_or_default(),
+      is_food_spawned: outcome.spawned_food().is_some(),
+      spawned: outcome.spawned_food().unwrap_or(Point(0, 0)),
+      is_dead,
+      death_cause,
+      death_point,
+      is_won: outcome.winner() == Some(0),
+    }
+  }
+}
+
+#[repr(C)]
+pub struct SnakeCtrlCState {
+  pub snake: *mut Point,
+  pub snake_len: usize,
+  pub food: *mut Point,
+  pub food_len: usize,
+  pub head_direction: Direction,
+  pub tail_direction: Direction,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnakeCtrlCPartKind {
+  Head,
+  Tail,
+  Body,
+  Corner,
+}
+
+/// One snake segment. `direction` is meaningful for `Head` and `Tail`,
+/// `is_vertical` for `Body` and `corner` for `Corner`.
+#[repr(C)]
+pub struct SnakeCtrlCPart {
+  pub point: Point,
+  pub kind: SnakeCtrlCPartKind,
+  pub direction: Direction,
+  pub is_vertical: bool,
+  pub corner: SnakeCornerVariant,
+}
+
+/// `food_kinds` has `food_len` items, one for every point of `food`.
+#[repr(C)]
+pub struct SnakeCtrlCFullState {
+  pub snake: *mut SnakeCtrlCPart,
+  pub snake_len: usize,
+  pub food: *mut Point,
+  pub food_kinds: *mut FoodKind,
+  pub food_len: usize,
+  pub direction: Direction,
+}
+
+fn guard<F: FnOnce() -> SnakeCtrlCode>(f: F) -> SnakeCtrlCode {
+  catch_unwind(AssertUnwindSafe(f)).unwrap_or(SnakeCtrlCode::Panic)
+}
+
+fn into_raw_parts<T>(v: Vec<T>) -> (*mut T, usize) {
+  let len = v.len();
+  let ptr = Box::into_raw(v.into_boxed_slice()) as *mut T;
+  (ptr, len)
+}
+
+unsafe fn free_raw_parts<T>(ptr: *mut T, len: usize) {
+  if !ptr.is_null() {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+  }
+}
+
+#[no_mangle]
+pub extern "C" fn snake_ctrl_options_default() -> SnakeCtrlCOptions {
+  let cfg = InnerCfg::from_options(&SnakeCtrlOptions::default());
+  SnakeCtrlCOptions {
+    dimension_x: cfg.dimension_x,
+    dimension_y: cfg.dimension_y,
+    initial_snake_size: cfg.initial_snake_size,
+    walking_through_the_walls: cfg.walking_through_the_walls,
+    fail_on_revert: cfg.fail_on_revert,
+    auto_gen_food: cfg.auto_gen_food,
+    use_seed: false,
+    seed: 0,
+    players: cfg.players,
+    death_rule: cfg.death_rule as u32,
+    walls: ptr::null(),
+    walls_len: 0,
+    growth_per_food: cfg.growth_per_food,
+    input_queue_depth: cfg.input_queue_depth,
+  }
+}
+
+/// Static, NUL-terminated description of `code`, a `SnakeCtrlCode`. Other
+/// values get a description too.
+#[no_mangle]
+pub extern "C" fn snake_ctrl_code_message(code: u32) -> *const c_char {
+  let code = SnakeCtrlCode::ALL.iter().find(|c| **c as u32 == code);
+  let msg: &'static [u8] = match code.copied() {
+    None => b"unknown code\0",
+    Some(code) => code_message(code),
+  };
+  msg.as_ptr() as *const c_char
+}
+
+fn code_message(code: SnakeCtrlCode) -> &'static [u8] {
+  match code {
+    SnakeCtrlCode::Ok => b"ok\0",
+    SnakeCtrlCode::RowIndexOutOfBounds => b"row index is out of bounds\0",
+    SnakeCtrlCode::ColumnIndexOutOfBounds => b"column index is out of bounds\0",
+    SnakeCtrlCode::InitSnakeSizeIsBig => {
+      b"initial snake size is more than possible\0"
+    }
+    SnakeCtrlCode::SnakeIsZero => b"snake size is zero\0",
+    SnakeCtrlCode::GameIsOver => b"the game is over, restart it first\0",
+    SnakeCtrlCode::SnapshotVersion => b"the snapshot version is unsupported\0",
+    SnakeCtrlCode::UnknownPlayer => b"there is no player with that id\0",
+    SnakeCtrlCode::TooManyPlayers => b"too many players for the board\0",
+    SnakeCtrlCode::SnakeBlocked => b"a snake is on a wall or another snake\0",
+    SnakeCtrlCode::SpawnBlocked => {
+      b"the snake start overlaps a wall, a portal or another snake\0"
+    }
+    SnakeCtrlCode::FoodBlocked => {
+      b"a food cell is on a wall, a portal, a snake or other food\0"
+    }
+    SnakeCtrlCode::BadPortal => {
+      b"a portal cell is on a wall or in another portal\0"
+    }
+    SnakeCtrlCode::BrokenSnake => {
+      b"a snake body is not a chain of cells behind its head\0"
+    }
+    SnakeCtrlCode::NullPointer => b"null pointer passed\0",
+    SnakeCtrlCode::BufferTooSmall => b"buffer is too small\0",
+    SnakeCtrlCode::Panic => b"internal panic\0",
+    SnakeCtrlCode::InvalidValue => b"an enum argument is out of range\0",
+  }
+}
+
+/// # Safety
+/// `opts` must be null or point to valid options with valid `walls`, `out`
+/// must be a valid pointer. On success `*out` holds a handle to release
+/// with `snake_ctrl_free`.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_new(
+  opts: *const SnakeCtrlCOptions,
+  out: *mut *mut SnakeCtrl,
+) -> SnakeCtrlCode {
+  if out.is_null() {
+    return SnakeCtrlCode::NullPointer;
+  }
+  guard(|| {
+    let opts = if opts.is_null() {
+      SnakeCtrlOptions::default()
+    } else {
+      match (*opts).to_options() {
+        Ok(opts) => opts,
+        Err(code) => return code,
+      }
+    };
+    match SnakeCtrl::new(&opts) {
+      Ok(ctrl) => {
+        *out = Box::into_raw(Box::new(ctrl));
+        SnakeCtrlCode::Ok
+      }
+      Err(e) => SnakeCtrlCode::from(&e),
+    }
+  })
+}
+
+/// # Safety
+/// `ctrl` must be null or a handle from `snake_ctrl_new` not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_free(ctrl: *mut SnakeCtrl) {
+  if !ctrl.is_null() {
+    drop(Box::from_raw(ctrl));
+  }
+}
+
+/// `direction` is a `Direction`, other values give `InvalidValue`.
+///
+/// # Safety
+/// `ctrl` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_direction_to(
+  ctrl: *mut SnakeCtrl,
+  direction: u32,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_mut() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  let direction = match Direction::try_from(direction) {
+    Ok(d) => d,
+    Err(code) => return code,
+  };
+  guard(|| match ctrl.direction_to(direction) {
+    Ok(()) => SnakeCtrlCode::Ok,
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// `direction` is a `Direction`, other values give `InvalidValue`.
+///
+/// # Safety
+/// `ctrl` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_direction_to_player(
+  ctrl: *mut SnakeCtrl,
+  player: usize,
+  direction: u32,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_mut() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  let direction = match Direction::try_from(direction) {
+    Ok(d) => d,
+    Err(code) => return code,
+  };
+  guard(|| match ctrl.direction_to_player(player, direction) {
+    Ok(()) => SnakeCtrlCode::Ok,
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// Moves the snake one cell. `tick` may be null, otherwise it receives
+/// what happened during the tick. Dying or winning is not an error, the
+/// next call after it returns `GameIsOver`.
+///
+/// # Safety
+/// `ctrl` must be a live handle, `tick` null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_next_tick(
+  ctrl: *mut SnakeCtrl,
+  tick: *mut SnakeCtrlCTick,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_mut() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| match ctrl.next_tick() {
+    Ok(outcome) => {
+      if !tick.is_null() {
+        *tick = SnakeCtrlCTick::new(ctrl, &outcome);
+      }
+      SnakeCtrlCode::Ok
+    }
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// # Safety
+/// `ctrl` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_restart(
+  ctrl: *mut SnakeCtrl,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_mut() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| match ctrl.restart() {
+    Ok(()) => SnakeCtrlCode::Ok,
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// Restarts the game with an explicit food seed.
+///
+/// # Safety
+/// `ctrl` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_restart_with_seed(
+  ctrl: *mut SnakeCtrl,
+  seed: u64,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_mut() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| match ctrl.restart_with_seed(seed) {
+    Ok(()) => SnakeCtrlCode::Ok,
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// # Safety
+/// `ctrl` must be a live handle, `seed` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_seed(
+  ctrl: *const SnakeCtrl,
+  seed: *mut u64,
+) -> SnakeCtrlCode {
+  match ctrl.as_ref() {
+    Some(c) if !seed.is_null() => {
+      *seed = c.seed();
+      SnakeCtrlCode::Ok
+    }
+    _ => SnakeCtrlCode::NullPointer,
+  }
+}
+
+/// # Safety
+/// `ctrl` must be a live handle, `direction` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_current_direction(
+  ctrl: *const SnakeCtrl,
+  direction: *mut Direction,
+) -> SnakeCtrlCode {
+  match ctrl.as_ref() {
+    Some(c) if !direction.is_null() => {
+      *direction = c.current_direction();
+      SnakeCtrlCode::Ok
+    }
+    _ => SnakeCtrlCode::NullPointer,
+  }
+}
+
+/// Fills `out` with a copy of the state. Release it with
+/// `snake_ctrl_state_free`.
+///
+/// # Safety
+/// `ctrl` must be a live handle, `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_get_state(
+  ctrl: *const SnakeCtrl,
+  reversed_y: bool,
+  out: *mut SnakeCtrlCState,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_ref() {
+    Some(c) if !out.is_null() => c,
+    _ => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| {
+    let state = if reversed_y {
+      ctrl.get_state_reversed_y()
+    } else {
+      ctrl.get_state()
+    };
+    let (snake, snake_len) = into_raw_parts(state.snake);
+    let (food, food_len) = into_raw_parts(state.food);
+    *out = SnakeCtrlCState {
+      snake,
+      snake_len,
+      food,
+      food_len,
+      head_direction: state.head_direction,
+      tail_direction: state.tail_direction,
+    };
+    SnakeCtrlCode::Ok
+  })
+}
+
+/// # Safety
+/// `state` must be null or filled by `snake_ctrl_get_state`.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_state_free(state: *mut SnakeCtrlCState) {
+  if let Some(s) = state.as_mut() {
+    free_raw_parts(s.snake, s.snake_len);
+    free_raw_parts(s.food, s.food_len);
+    s.snake = ptr::null_mut();
+    s.snake_len = 0;
+    s.food = ptr::null_mut();
+    s.food_len = 0;
+  }
+}
+
+/// Fills `out` with the segment-by-segment state. Release it with
+/// `snake_ctrl_full_state_free`.
+///
+/// # Safety
+/// `ctrl` must be a live handle, `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_get_full_state(
+  ctrl: *const SnakeCtrl,
+  reversed_y: bool,
+  out: *mut SnakeCtrlCFullState,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_ref() {
+    Some(c) if !out.is_null() => c,
+    _ => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| {
+    let state = if reversed_y {
+      ctrl.get_full_state_reversed_y()
+    } else {
+      ctrl.get_full_state()
+    };
+    let parts = state
+      .snake
+      .iter()
+      .map(|p| {
+        let mut part = SnakeCtrlCPart {
+          point: p.point,
+          kind: SnakeCtrlCPartKind::Body,
+          direction: state.direction,
+          is_vertical: false,
+          corner: SnakeCornerVariant::TopLeft,
+        };
+        match p.variant {
+          SnakePartVariant::Head(d) => {
+            part.kind = SnakeCtrlCPartKind::Head;
+            part.direction = d;
+          }
+          SnakePartVariant::Tail(d) => {
+            part.kind = SnakeCtrlCPartKind::Tail;
+            part.direction = d;
+          }
+          SnakePartVariant::Body(is_vertical) => {
+            part.is_vertical = is_vertical;
+          }
+          SnakePartVariant::Corner(c) => {
+            part.kind = SnakeCtrlCPartKind::Corner;
+            part.corner = c;
+          }
+        }
+        part
+      })
+      .collect();
+    let (snake, snake_len) = into_raw_parts(parts);
+    let kinds = state.food.iter().map(|f| f.kind).collect();
+    let (food_kinds, _) = into_raw_parts(kinds);
+    let points = state.food.iter().map(|f| f.point).collect();
+    let (food, food_len) = into_raw_parts(points);
+    *out = SnakeCtrlCFullState {
+      snake,
+      snake_len,
+      food,
+      food_kinds,
+      food_len,
+      direction: state.direction,
+    };
+    SnakeCtrlCode::Ok
+  })
+}
+
+/// # Safety
+/// `state` must be null or filled by `snake_ctrl_get_full_state`.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_full_state_free(
+  state: *mut SnakeCtrlCFullState,
+) {
+  if let Some(s) = state.as_mut() {
+    free_raw_parts(s.snake, s.snake_len);
+    free_raw_parts(s.food, s.food_len);
+    free_raw_parts(s.food_kinds, s.food_len);
+    s.snake = ptr::null_mut();
+    s.snake_len = 0;
+    s.food = ptr::null_mut();
+    s.food_kinds = ptr::null_mut();
+    s.food_len = 0;
+  }
+}
+
+/// Copies the matrix row by row from `y = 0` into `buf` (`dimension_x *
+/// dimension_y` cells: 0 empty, 1 snake, 7 apple, 8 wall, 9 golden apple,
+/// 10 shrink, 11 speed up, 12 slow down, 13 poison). The dimensions are written to
+/// `dim_x` and `dim_y` when they are not null, so a call with a null `buf`
+/// can be used to size the buffer.
+///
+/// # Safety
+/// `ctrl` must be a live handle, `buf` null or valid for `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_get_matrix(
+  ctrl: *const SnakeCtrl,
+  buf: *mut u8,
+  buf_len: usize,
+  dim_x: *mut u16,
+  dim_y: *mut u16,
+) -> SnakeCtrlCode {
+  let ctrl = match ctrl.as_ref() {
+    Some(c) => c,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| {
+    let matrix = ctrl.get_matrix();
+    let (cols, rows) = matrix.dimensions();
+    if !dim_x.is_null() {
+      *dim_x = cols;
+    }
+    if !dim_y.is_null() {
+      *dim_y = rows;
+    }
+    if buf.is_null() {
+      return SnakeCtrlCode::Ok;
+    }
+    let codes = matrix.to_codes();
+    if buf_len < codes.len() {
+      return SnakeCtrlCode::BufferTooSmall;
+    }
+    std::slice::from_raw_parts_mut(buf, codes.len()).copy_from_slice(&codes);
+    SnakeCtrlCode::Ok
+  })
+}
+
+/// Observation encoder of a `SnakeCtrlEnv`.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SnakeCtrlEnvEncoder {
+  /// `Features::SIZE` flags.
+  Features,
+  /// One-hot planes of the board, see `Matrix::one_hot`.
+  GridPlanes,
+}
+
+/// Result of one `snake_ctrl_env_step`. `death_cause` is meaningful only
+/// when `is_dead` is set.
+#[repr(C)]
+pub struct SnakeCtrlCStep {
+  pub reward: f32,
+  pub done: bool,
+  pub truncated: bool,
+  pub score: u64,
+  pub length: usize,
+  pub ticks: u64,
+  pub ate: bool,
+  pub is_dead: bool,
+  pub death_cause: DeathCause,
+  pub is_won: bool,
+}
+
+impl SnakeCtrlCStep {
+  fn new(reward: f32, done: bool, info: &StepInfo) -> Self {
+    SnakeCtrlCStep {
+      reward,
+      done,
+      truncated: info.truncated,
+      score: info.score,
+      length: info.length,
+      ticks: info.ticks,
+      ate: info.ate,
+      is_dead: info.death.is_some(),
+      death_cause: info.death.unwrap_or(DeathCause::AteItself),
+      is_won: info.is_won,
+    }
+  }
+}
+
+/// Copies the observation into `buf` when it is not null.
+unsafe fn write_observation(
+  obs: &[f32],
+  buf: *mut f32,
+  buf_len: usize,
+) -> SnakeCtrlCode {
+  if buf.is_null() {
+    return SnakeCtrlCode::Ok;
+  }
+  if buf_len < obs.len() {
+    return SnakeCtrlCode::BufferTooSmall;
+  }
+  std::slice::from_raw_parts_mut(buf, obs.len()).copy_from_slice(obs);
+  SnakeCtrlCode::Ok
+}
+
+#[no_mangle]
+pub extern "C" fn snake_ctrl_rewards_default() -> Rewards {
+  Rewards::default()
+}
+
+/// Creates a training environment. `opts` and `rewards` may be null for
+/// the defaults, `max_ticks` of 0 never truncates an episode. `encoder`
+/// is a `SnakeCtrlEnvEncoder`, other values give `InvalidValue`.
+///
+/// # Safety
+/// `opts` and `rewards` must be null or valid, `out` a valid pointer. On
+/// success `*out` holds a handle to release with `snake_ctrl_env_free`.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_env_new(
+  opts: *const SnakeCtrlCOptions,
+  encoder: u32,
+  rewards: *const Rewards,
+  max_ticks: u64,
+  out: *mut *mut Env,
+) -> SnakeCtrlCode {
+  if out.is_null() {
+    return SnakeCtrlCode::NullPointer;
+  }
+  guard(|| {
+    let opts = if opts.is_null() {
+      SnakeCtrlOptions::default()
+    } else {
+      match (*opts).to_options() {
+        Ok(opts) => opts,
+        Err(code) => return code,
+      }
+    };
+    let env = match Env::new(&opts) {
+      Ok(env) => env,
+      Err(e) => return SnakeCtrlCode::from(&e),
+    };
+    let encoder = match SnakeCtrlEnvEncoder::try_from(encoder) {
+      Ok(e) => e,
+      Err(code) => return code,
+    };
+    let mut env = match encoder {
+      SnakeCtrlEnvEncoder::Features => env.encoder(Box::new(Features)),
+      SnakeCtrlEnvEncoder::GridPlanes => env.encoder(Box::new(GridPlanes)),
+    };
+    if let Some(rewards) = rewards.as_ref() {
+      env = env.rewards(*rewards);
+    }
+    if max_ticks > 0 {
+      env = env.max_ticks(max_ticks);
+    }
+    *out = Box::into_raw(Box::new(env));
+    SnakeCtrlCode::Ok
+  })
+}
+
+/// # Safety
+/// `env` must be null or a handle from `snake_ctrl_env_new` not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_env_free(env: *mut Env) {
+  if !env.is_null() {
+    drop(Box::from_raw(env));
+  }
+}
+
+/// Number of floats in every observation of `env`.
+///
+/// # Safety
+/// `env` must be a live handle, `size` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_env_observation_size(
+  env: *const Env,
+  size: *mut usize,
+) -> SnakeCtrlCode {
+  match env.as_ref() {
+    Some(e) if !size.is_null() => guard(|| {
+      *size = e.observation_size();
+      SnakeCtrlCode::Ok
+    }),
+    _ => SnakeCtrlCode::NullPointer,
+  }
+}
+
+/// Starts an episode on `seed` and copies its first observation into
+/// `obs` when it is not null.
+///
+/// # Safety
+/// `env` must be a live handle, `obs` null or valid for `obs_len` floats.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_env_reset(
+  env: *mut Env,
+  seed: u64,
+  obs: *mut f32,
+  obs_len: usize,
+) -> SnakeCtrlCode {
+  let env = match env.as_mut() {
+    Some(e) => e,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  guard(|| match env.reset(seed) {
+    Ok(o) => write_observation(&o, obs, obs_len),
+    Err(e) => SnakeCtrlCode::from(&e),
+  })
+}
+
+/// Plays one action. The observation goes into `obs` when it is not null
+/// and the rest into `step` when it is not null. A too small `obs` is
+/// reported before the game moves. Once `done` is set the next call
+/// returns `GameIsOver` until `snake_ctrl_env_reset`. `action` is a
+/// `SnakeCtrlAction`, other values give `InvalidValue`.
+///
+/// # Safety
+/// `env` must be a live handle, `obs` null or valid for `obs_len` floats,
+/// `step` null or valid.
+#[no_mangle]
+pub unsafe extern "C" fn snake_ctrl_env_step(
+  env: *mut Env,
+  action: u32,
+  obs: *mut f32,
+  obs_len: usize,
+  step: *mut SnakeCtrlCStep,
+) -> SnakeCtrlCode {
+  let env = match env.as_mut() {
+    Some(e) => e,
+    None => return SnakeCtrlCode::NullPointer,
+  };
+  let action = match Action::try_from(action) {
+    Ok(a) => a,
+    Err(code) => return code,
+  };
+  guard(|| {
+    if !obs.is_null() && obs_len < env.observation_size() {
+      return SnakeCtrlCode::BufferTooSmall;
+    }
+    match env.step(action) {
+      Ok((o, reward, done, info)) => {
+        if !step.is_null() {
+          *step = SnakeCtrlCStep::new(reward, done, &info);
+        }
+        write_observation(&o, obs, obs_len)
+      }
+      Err(e) => SnakeCtrlCode::from(&e),
+    }
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::ffi::CStr;
+
+  #[test]
+  fn handle_roundtrip() {
+    unsafe {
+      let mut opts = snake_ctrl_options_default();
+      opts.dimension_x = 12;
+      opts.dimension_y = 8;
+      opts.auto_gen_food = false;
+      let mut ctrl = ptr::null_mut();
+      assert_eq!(snake_ctrl_new(&opts, &mut ctrl), SnakeCtrlCode::Ok);
+
+      let mut tick = std::mem::zeroed::<SnakeCtrlCTick>();
+      assert_eq!(snake_ctrl_next_tick(ctrl, &mut tick), SnakeCtrlCode::Ok);
+      assert_eq!(tick.head, Point(7, 4));
+      assert!(!tick.is_dead);
+
+      let mut state = std::mem::zeroed::<SnakeCtrlCState>();
+      assert_eq!(
+        snake_ctrl_get_state(ctrl, false, &mut state),
+        SnakeCtrlCode::Ok
+      );
+      assert_eq!(state.snake_len, 3);
+      snake_ctrl_state_free(&mut state);
+
+      let (mut x, mut y) = (0, 0);
+      let mut buf = vec![0u8; 12 * 8];
+      assert_eq!(
+        snake_ctrl_get_matrix(ctrl, buf.as_mut_ptr(), 4, &mut x, &mut y),
+        SnakeCtrlCode::BufferTooSmall
+      );
+      assert_eq!((x, y), (12, 8));
+      assert_eq!(
+        snake_ctrl_get_matrix(
+          ctrl,
+          buf.as_mut_ptr(),
+          buf.len(),
+          ptr::null_mut(),
+          ptr::null_mut()
+        ),
+        SnakeCtrlCode::Ok
+      );
+      assert_eq!(buf.iter().filter(|c| **c == 1).count(), 3);
+
+      snake_ctrl_free(ctrl);
+    }
+  }
+
+  #[test]
+  fn maps_errors() {
+    unsafe {
+      let mut opts = snake_ctrl_options_default();
+      opts.initial_snake_size = 50;
+      let mut ctrl = ptr::null_mut();
+      assert_eq!(
+        snake_ctrl_new(&opts, &mut ctrl),
+        SnakeCtrlCode::InitSnakeSizeIsBig
+      );
+      assert!(ctrl.is_null());
+      assert_eq!(
+        snake_ctrl_next_tick(ptr::null_mut(), ptr::null_mut()),
+        SnakeCtrlCode::NullPointer
+      );
+      assert_eq!(Direction::try_from(3), Ok(Direction::Left));
+      assert_eq!(Direction::try_from(4), Err(SnakeCtrlCode::InvalidValue));
+      let msg = CStr::from_ptr(snake_ctrl_code_message(1000));
+      assert_eq!(msg.to_bytes(), b"unknown code");
+    }
+  }
+}
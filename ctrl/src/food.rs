@@ -0,0 +1,148 @@
+use crate::options::InnerCfg;
+use crate::Point;
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+/// What lies on the board and what it does to the snake that eats it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(eq, eq_int))]
+pub enum FoodKind {
+  /// One cell longer, one point.
+  #[default]
+  Apple,
+  /// One cell longer, five points.
+  Golden,
+  /// Two cells shorter, down to two cells.
+  Shrink,
+  /// One cell longer, the game should make ticks shorter.
+  SpeedUp,
+  /// One cell longer, the game should make ticks longer.
+  SlowDown,
+  /// Kills the snake.
+  Poison,
+}
+
+impl FoodKind {
+  pub const ALL: [FoodKind; 6] = [
+    FoodKind::Apple,
+    FoodKind::Golden,
+    FoodKind::Shrink,
+    FoodKind::SpeedUp,
+    FoodKind::SlowDown,
+    FoodKind::Poison,
+  ];
+
+  /// Cells the snake gets, negative when it loses cells.
+  pub fn growth(&self) -> i32 {
+    match self {
+      FoodKind::Shrink => -2,
+      FoodKind::Poison => 0,
+      _ => 1,
+    }
+  }
+
+  pub fn score(&self) -> u64 {
+    match self {
+      FoodKind::Golden => 5,
+      FoodKind::Poison => 0,
+      _ => 1,
+    }
+  }
+
+  pub fn is_deadly(&self) -> bool {
+    *self == FoodKind::Poison
+  }
+}
+
+/// Food on the board.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Food {
+  pub point: Point,
+  pub kind: FoodKind,
+  /// Ticks before the food disappears, `None` when it stays until eaten.
+  pub ticks_left: Option<u32>,
+}
+
+impl Food {
+  pub fn apple(point: Point) -> Self {
+    Food {
+      point,
+      kind: FoodKind::Apple,
+      ticks_left: None,
+    }
+  }
+}
+
+/// How often a kind of food appears and how long it stays.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoodSpawn {
+  pub kind: FoodKind,
+  /// Chance relative to the weights of the other kinds.
+  pub weight: u32,
+  /// Ticks before it disappears, `None` to stay until eaten.
+  pub lifetime: Option<u32>,
+}
+
+impl FoodSpawn {
+  pub fn new(kind: FoodKind, weight: u32) -> Self {
+    FoodSpawn {
+      kind,
+      weight,
+      lifetime: None,
+    }
+  }
+
+  pub fn lifetime(mut self, ticks: u32) -> Self {
+    self.lifetime = Some(ticks);
+    self
+  }
+}
+
+/// Picks a random cell that `is_free` accepts, or `None` when the board
+/// has no free cell left. `is_free` gets the cell index, `y * dim_x + x`,
+/// and the cell.
+pub(crate) fn free_cell<F: Fn(usize, Point) -> bool>(
+  cfg: &InnerCfg,
+  rng: &mut Pcg32,
+  is_free: F,
+) -> Option<Point> {
+  let max_x = usize::from(cfg.dimension_x);
+  let max_y = usize::from(cfg.dimension_y);
+  let point = |ind: usize| Point((ind % max_x) as u16, (ind / max_x) as u16);
+
+  let free_cells: Vec<usize> = (0..max_x * max_y)
+    .filter(|ind| is_free(*ind, point(*ind)))
+    .collect();
+
+  if free_cells.is_empty() {
+    return None;
+  }
+
+  let ind = free_cells[rng.gen_range(0, free_cells.len())];
+  Some(point(ind))
+}
+
+/// Picks the kind of new food by weight. The RNG is not touched when there
+/// is nothing to choose from, so games with apples only keep their food.
+pub(crate) fn pick_spawn(cfg: &InnerCfg, rng: &mut Pcg32) -> FoodSpawn {
+  let total: u32 = cfg.food_spawns.iter().map(|s| s.weight).sum();
+  if cfg.food_spawns.len() < 2 || total == 0 {
+    return cfg
+      .food_spawns
+      .first()
+      .copied()
+      .unwrap_or_else(|| FoodSpawn::new(FoodKind::Apple, 1));
+  }
+  let mut roll = rng.gen_range(0, total);
+  for spawn in &cfg.food_spawns {
+    if roll < spawn.weight {
+      return *spawn;
+    }
+    roll -= spawn.weight;
+  }
+  cfg.food_spawns[0]
+}
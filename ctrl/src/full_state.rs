@@ -1,6 +1,8 @@
-use crate::{Direction, Point};
+use crate::{Direction, Food, Point};
 
+#[repr(C)]
 #[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(eq, eq_int))]
 pub enum SnakeCornerVariant {
   TopLeft,
   TopRight,
@@ -42,21 +44,73 @@ impl SnakePart {
 
 pub struct SnakeCtrlFullState {
   pub snake: Vec<SnakePart>,
-  pub food: Vec<Point>,
+  pub food: Vec<Food>,
   pub direction: Direction,
 }
 
+/// How the board joins its cells, to tell how a snake got from one segment
+/// to the next when they are not side by side.
+pub(crate) struct Layout<'a> {
+  pub(crate) dim_x: u16,
+  pub(crate) dim_y: u16,
+  pub(crate) portals: &'a [(Point, Point)],
+}
+
+impl Layout<'_> {
+  /// Direction of the move from `from` to `to`: a step to a near cell, a
+  /// step through an edge or a step into a portal that leads to `to`.
+  pub(crate) fn step_direction(
+    &self,
+    from: &Point,
+    to: &Point,
+  ) -> Option<Direction> {
+    if from.is_next_to(to) {
+      return to.offset_from_near(from);
+    }
+    let through_x = from.1 == to.1
+      && u32::from(from.0.abs_diff(to.0)) + 1 == u32::from(self.dim_x);
+    let through_y = from.0 == to.0
+      && u32::from(from.1.abs_diff(to.1)) + 1 == u32::from(self.dim_y);
+    if through_x || through_y {
+      return from.offset_from_near(to);
+    }
+    self.portals.iter().find_map(|(a, b)| {
+      let entry = if to == b {
+        a
+      } else if to == a {
+        b
+      } else {
+        return None;
+      };
+      if from.is_next_to(entry) {
+        entry.offset_from_near(from)
+      } else {
+        None
+      }
+    })
+  }
+}
+
 pub(crate) fn calc_full_state(
   snake: &[Point],
-  food: &[Point],
+  food: &[Food],
   current_direction: Direction,
-  dim_y: u16,
+  layout: &Layout,
   reverse_y: bool,
 ) -> SnakeCtrlFullState {
+  let dim_y = layout.dim_y;
   let snake_len = snake.len();
   let max_ind = snake.len() - 1;
   let mut result: Vec<SnakePart> = Vec::with_capacity(snake_len);
 
+  // The move that brought the segment `ind` from where the one behind it
+  // is now.
+  let moved = |ind: usize| {
+    layout
+      .step_direction(&snake[ind + 1], &snake[ind])
+      .unwrap_or(current_direction)
+  };
+
   for (ind, curr_point) in snake.iter().enumerate() {
     if ind == 0 {
       result.push(SnakePart::new(
@@ -64,27 +118,21 @@ pub(crate) fn calc_full_state(
         SnakePartVariant::Head(current_direction),
       ));
     } else if ind == max_ind {
-      let pre_tail = snake.get(max_ind - 1).unwrap();
-      let mut tail_direction = curr_point.offset_from_near(&pre_tail).unwrap();
-      if !pre_tail.is_near_with(&curr_point) {
-        tail_direction = tail_direction.opposite_direction();
-      }
+      let tail_direction = moved(ind - 1).opposite_direction();
       result.push(SnakePart::new(
         *curr_point,
         SnakePartVariant::Tail(tail_direction),
       ))
     } else {
-      let prev = snake.get(ind + 1).unwrap();
-      let next = snake.get(ind - 1).unwrap();
       result.push(SnakePart::new(
         *curr_point,
-        get_body_part_variant(prev, curr_point, next),
+        get_body_part_variant(moved(ind), moved(ind - 1)),
       ))
     }
   }
 
   let mut f = food.to_owned();
-  f.iter_mut().for_each(|p| p.reverse_y(dim_y));
+  f.iter_mut().for_each(|f| f.point.reverse_y(dim_y));
 
   if reverse_y {
     result.iter_mut().for_each(|p| {
@@ -103,27 +151,18 @@ pub(crate) fn calc_full_state(
   }
 }
 
+/// `offset_from_prev` is the move into the segment, `offset_from_next` the
+/// move out of it.
 fn get_body_part_variant(
-  prev: &Point,
-  curr: &Point,
-  next: &Point,
+  offset_from_prev: Direction,
+  offset_from_next: Direction,
 ) -> SnakePartVariant {
-  let mut offset_from_prev = curr.offset_from_near(prev).unwrap();
-  let mut offset_from_next = next.offset_from_near(curr).unwrap();
-
   if offset_from_prev == offset_from_next
     || offset_from_prev == offset_from_next.opposite_direction()
   {
     return SnakePartVariant::Body(offset_from_prev.is_vertical());
   }
 
-  if !next.is_near_with(curr) {
-    offset_from_next = offset_from_next.opposite_direction();
-  }
-  if !prev.is_near_with(curr) {
-    offset_from_prev = offset_from_prev.opposite_direction();
-  }
-
   match offset_from_prev {
     Direction::Top => {
       if offset_from_next == Direction::Right {
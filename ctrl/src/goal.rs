@@ -0,0 +1,62 @@
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// What the first player has to do to finish a level.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Goal {
+  /// Eat the given number of apples.
+  EatApples(u64),
+  /// Grow up to the given length.
+  ReachLength(usize),
+  /// Stay alive for the given number of ticks.
+  SurviveTicks(u64),
+}
+
+/// How far the player is from a goal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GoalProgress {
+  pub current: u64,
+  pub target: u64,
+}
+
+impl GoalProgress {
+  pub fn is_reached(&self) -> bool {
+    self.current >= self.target
+  }
+}
+
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+#[error("expected `apples N`, `length N` or `ticks N`")]
+pub struct ParseGoalError;
+
+/// Parses `apples N`, `length N` and `ticks N`.
+impl FromStr for Goal {
+  type Err = ParseGoalError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut words = s.split_whitespace();
+    let (kind, num) = match (words.next(), words.next(), words.next()) {
+      (Some(kind), Some(num), None) => (kind, num),
+      _ => return Err(ParseGoalError),
+    };
+    let num: u64 = num.parse().map_err(|_| ParseGoalError)?;
+    match kind {
+      "apples" => Ok(Goal::EatApples(num)),
+      "length" => Ok(Goal::ReachLength(num as usize)),
+      "ticks" => Ok(Goal::SurviveTicks(num)),
+      _ => Err(ParseGoalError),
+    }
+  }
+}
+
+impl fmt::Display for Goal {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Goal::EatApples(n) => write!(f, "Eat {} apples", n),
+      Goal::ReachLength(n) => write!(f, "Grow to length {}", n),
+      Goal::SurviveTicks(n) => write!(f, "Survive {} ticks", n),
+    }
+  }
+}
@@ -0,0 +1,301 @@
+//! Plain-text levels.
+//!
+//! A level is an optional header of `key: value` lines, a `---` line and
+//! an ASCII grid, top row first:
+//!
+//! ```text
+//! name: Corridor
+//! wrap: false
+//! speed: 120
+//! direction: right
+//! ---
+//! ##########
+//! #S     A #
+//! ##########
+//! ```
+//!
+//! `#` is a wall, `S` a snake start, `A` an apple, a space or `.` an empty
+//! cell. A digit marks a portal, every digit used must appear twice and
+//! links its two cells.
+//!
+//! Header keys are `name`, `wrap` (`true`, `false`, `horizontal`,
+//! `vertical` or edges like `left, right`), `speed` (tick length in ms, for
+//! the game), `size` (initial snake size), `direction` (`up`, `down`,
+//! `left` or `right`, where every snake faces), `goal` (`apples N`,
+//! `length N` or `ticks N`), `growth` (cells per apple), `auto_food`,
+//! `fail_on_revert` and `seed`.
+
+use crate::options::SnakeStart;
+use crate::{
+  Direction, Goal, Point, SnakeCtrl, SnakeCtrlOptions, SnakeCtrlResult,
+};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A parse error, `line` and `column` start from 1.
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+#[error("line {line}, column {column}: {kind}")]
+pub struct LevelError {
+  pub line: usize,
+  pub column: usize,
+  pub kind: LevelErrorKind,
+}
+
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+pub enum LevelErrorKind {
+  #[error("expected `key: value`")]
+  BadHeaderLine,
+  #[error("unknown option `{0}`")]
+  UnknownOption(String),
+  #[error("bad value `{value}` for `{key}`")]
+  BadValue { key: String, value: String },
+  #[error("unexpected character `{0}`")]
+  UnexpectedChar(char),
+  #[error("the row is {found} cells wide, {expected} expected")]
+  RaggedRow { found: usize, expected: usize },
+  #[error("the level has no cells")]
+  Empty,
+  #[error("the level is wider or higher than {0} cells")]
+  TooBig(u16),
+  #[error("the level has no snake start `S`")]
+  NoStart,
+  #[error("the level has more than {0} snake starts")]
+  TooManyStarts(u8),
+  #[error("portal `{0}` needs exactly two cells")]
+  UnpairedPortal(char),
+}
+
+#[derive(Clone)]
+pub struct Level {
+  name: Option<String>,
+  speed: Option<u64>,
+  goal: Option<Goal>,
+  dimension_x: u16,
+  dimension_y: u16,
+  options: SnakeCtrlOptions,
+}
+
+fn err(line: usize, column: usize, kind: LevelErrorKind) -> LevelError {
+  LevelError { line, column, kind }
+}
+
+/// Header value with the position of its first character.
+struct Value<'a> {
+  line: usize,
+  column: usize,
+  key: &'a str,
+  text: &'a str,
+}
+
+impl Value<'_> {
+  fn bad(&self) -> LevelError {
+    let kind = LevelErrorKind::BadValue {
+      key: self.key.to_owned(),
+      value: self.text.to_owned(),
+    };
+    err(self.line, self.column, kind)
+  }
+
+  fn parse<T: FromStr>(&self) -> Result<T, LevelError> {
+    self.text.parse().map_err(|_| self.bad())
+  }
+}
+
+impl Level {
+  pub fn parse(src: &str) -> Result<Self, LevelError> {
+    let lines: Vec<&str> = src.lines().collect();
+    let separator = lines.iter().position(|l| l.trim_end() == "---");
+    let (header, grid_start) = match separator {
+      Some(ind) => (&lines[..ind], ind + 1),
+      None => (&lines[..0], 0),
+    };
+
+    let mut level = Level {
+      name: None,
+      speed: None,
+      goal: None,
+      dimension_x: 0,
+      dimension_y: 0,
+      options: SnakeCtrlOptions::default(),
+    };
+    let mut direction = Direction::Right;
+    for (ind, raw) in header.iter().enumerate() {
+      let line = ind + 1;
+      let trimmed = raw.trim();
+      if trimmed.is_empty() || trimmed.starts_with(';') {
+        continue;
+      }
+      let (key, value) = trimmed
+        .split_once(':')
+        .ok_or_else(|| err(line, 1, LevelErrorKind::BadHeaderLine))?;
+      let key_column = raw.len() - raw.trim_start().len() + 1;
+      let text = value.trim();
+      let value = Value {
+        line,
+        column: key_column + key.len() + 1 + value.len()
+          - value.trim_start().len(),
+        key: key.trim(),
+        text,
+      };
+      let opts = std::mem::take(&mut level.options);
+      level.options = match value.key {
+        "name" => {
+          level.name = Some(text.to_owned());
+          opts
+        }
+        "speed" => {
+          level.speed = Some(value.parse()?);
+          opts
+        }
+        "goal" => {
+          level.goal = Some(value.parse()?);
+          opts
+        }
+        "wrap" => opts.wrap_edges(value.parse()?),
+        "size" => opts.initial_snake_size(value.parse()?),
+        "growth" => opts.growth_per_food(value.parse()?),
+        "auto_food" => opts.auto_gen_food(value.parse()?),
+        "fail_on_revert" => opts.fail_on_revert(value.parse()?),
+        "seed" => opts.seed(value.parse()?),
+        "direction" => {
+          direction = match text {
+            "up" => Direction::Top,
+            "down" => Direction::Bottom,
+            "left" => Direction::Left,
+            "right" => Direction::Right,
+            _ => return Err(value.bad()),
+          };
+          opts
+        }
+        key => {
+          let kind = LevelErrorKind::UnknownOption(key.to_owned());
+          return Err(err(line, key_column, kind));
+        }
+      };
+    }
+
+    let mut rows: Vec<&str> = lines[grid_start..].to_vec();
+    while rows.last().is_some_and(|r| r.trim().is_empty()) {
+      rows.pop();
+    }
+    let first_line = grid_start + 1;
+    if rows.is_empty() {
+      return Err(err(first_line, 1, LevelErrorKind::Empty));
+    }
+    let max = u16::MAX as usize;
+    let width = rows[0].chars().count();
+    if width == 0 || width > max || rows.len() > max {
+      let kind = if width == 0 {
+        LevelErrorKind::Empty
+      } else {
+        LevelErrorKind::TooBig(u16::MAX)
+      };
+      return Err(err(first_line, 1, kind));
+    }
+    let height = rows.len() as u16;
+
+    let mut walls = vec![];
+    let mut starts = vec![];
+    let mut food = vec![];
+    let mut portal_cells: BTreeMap<char, Vec<(Point, usize, usize)>> =
+      BTreeMap::new();
+    for (row_ind, row) in rows.iter().enumerate() {
+      let line = first_line + row_ind;
+      let y = height - 1 - row_ind as u16;
+      let mut cells = 0;
+      for (col_ind, ch) in row.chars().enumerate() {
+        cells += 1;
+        let p = Point(col_ind as u16, y);
+        match ch {
+          '#' => walls.push(p),
+          'S' => starts.push(SnakeStart::new(p, direction)),
+          'A' => food.push(p),
+          '0'..='9' => {
+            let cell = (p, line, col_ind + 1);
+            portal_cells.entry(ch).or_default().push(cell);
+          }
+          ' ' | '.' => {}
+          _ => {
+            let kind = LevelErrorKind::UnexpectedChar(ch);
+            return Err(err(line, col_ind + 1, kind));
+          }
+        }
+      }
+      if cells != width {
+        let kind = LevelErrorKind::RaggedRow {
+          found: cells,
+          expected: width,
+        };
+        return Err(err(line, cells.min(width) + 1, kind));
+      }
+    }
+
+    let last_line = first_line + rows.len() - 1;
+    if starts.is_empty() {
+      return Err(err(last_line, 1, LevelErrorKind::NoStart));
+    }
+    if starts.len() > usize::from(u8::MAX) {
+      return Err(err(last_line, 1, LevelErrorKind::TooManyStarts(u8::MAX)));
+    }
+
+    let mut portals = vec![];
+    for (ch, cells) in portal_cells {
+      match cells[..] {
+        [(a, ..), (b, ..)] => portals.push((a, b)),
+        _ => {
+          let (_, line, column) = cells[0];
+          return Err(err(line, column, LevelErrorKind::UnpairedPortal(ch)));
+        }
+      }
+    }
+
+    level.dimension_x = width as u16;
+    level.dimension_y = height;
+    level.options = level
+      .options
+      .dimension_x(level.dimension_x)
+      .dimension_y(level.dimension_y)
+      .players(starts.len() as u8)
+      .walls(walls)
+      .starts(starts)
+      .food(food)
+      .portals(portals);
+    Ok(level)
+  }
+
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+
+  /// Tick length in milliseconds asked by the level.
+  pub fn speed(&self) -> Option<u64> {
+    self.speed
+  }
+
+  /// What the player has to do to finish the level.
+  pub fn goal(&self) -> Option<Goal> {
+    self.goal
+  }
+
+  pub fn dimensions(&self) -> (u16, u16) {
+    (self.dimension_x, self.dimension_y)
+  }
+
+  /// Options with the board of the level, ready for `SnakeCtrl::new`.
+  pub fn options(&self) -> &SnakeCtrlOptions {
+    &self.options
+  }
+
+  pub fn new_ctrl(&self) -> SnakeCtrlResult<SnakeCtrl> {
+    SnakeCtrl::new(&self.options)
+  }
+}
+
+impl FromStr for Level {
+  type Err = LevelError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Level::parse(s)
+  }
+}
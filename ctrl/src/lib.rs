@@ -1,21 +1,54 @@
+pub mod ai;
 mod board;
+pub mod env;
 mod err;
+pub mod ffi;
+mod food;
 mod full_state;
+mod goal;
+pub mod level;
 mod matrix;
+#[cfg(feature = "net")]
+pub mod net;
+mod observer;
+mod occupancy;
 mod options;
+#[cfg(feature = "pyo3")]
+pub mod py;
+mod replay;
+mod sim;
+#[cfg(feature = "serde")]
+mod snapshot;
+mod tick;
 
-use crate::full_state::calc_full_state;
+use crate::full_state::{calc_full_state, Layout};
 pub use crate::full_state::{
   SnakeCornerVariant, SnakeCtrlFullState, SnakePart, SnakePartVariant,
 };
-pub use crate::matrix::Matrix as SnakeCtrlMatrix;
+pub use crate::matrix::{Cell, Glyphs, Matrix as SnakeCtrlMatrix};
+#[cfg(feature = "net")]
+use crate::net::protocol::{NetFrame, NetSnake};
 use crate::options::InnerCfg;
 use board::Board;
 pub use err::{SnakeCtrlErr, SnakeCtrlResult};
+pub use food::{Food, FoodKind, FoodSpawn};
+pub use goal::{Goal, GoalProgress, ParseGoalError};
+pub use observer::Observer;
+use observer::{notify, Observers};
+pub use options::DeathRule;
 pub use options::Options as SnakeCtrlOptions;
-use std::rc::Rc;
+pub use options::{ParseWrapEdgesError, SnakeStart, WrapEdges};
+pub use replay::{Replay, ReplayInput, ReplayPlayer};
+pub use sim::{GameStats, Observation, Policy, Rollout, SimSummary, Simulator};
+#[cfg(feature = "serde")]
+pub use snapshot::SnakeCtrlSnapshot;
+use std::sync::Arc;
+pub use tick::{DeathCause, TickEvent, TickOutcome};
 
+#[repr(C)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(eq, eq_int))]
 pub enum Direction {
   Top,
   Right,
@@ -44,7 +77,9 @@ impl Direction {
   }
 }
 
+#[repr(C)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point(pub u16, pub u16);
 impl Point {
   pub fn is_near_with(&self, p: &Point) -> bool {
@@ -55,6 +90,11 @@ impl Point {
     }
   }
 
+  /// Shares a side with `p`.
+  pub fn is_next_to(&self, p: &Point) -> bool {
+    self.0.abs_diff(p.0) + self.1.abs_diff(p.1) == 1
+  }
+
   pub fn offset_from_near(&self, p: &Point) -> Option<Direction> {
     if self.0 == p.0 {
       return Some(if self.1 < p.1 {
@@ -78,11 +118,28 @@ impl Point {
   }
 }
 
+/// A game. Cloning it is cheap, the clone plays on by itself from the same
+/// state with no observers and no recording.
 pub struct SnakeCtrl {
-  next_direction: Direction,
-  current_direction: Direction,
   board: Board,
-  cfg: Rc<InnerCfg>,
+  cfg: Arc<InnerCfg>,
+  is_over: bool,
+  tick: u64,
+  recording: Option<Replay>,
+  observers: Observers,
+}
+
+impl Clone for SnakeCtrl {
+  fn clone(&self) -> Self {
+    SnakeCtrl {
+      board: self.board.clone(),
+      cfg: self.cfg.clone(),
+      is_over: self.is_over,
+      tick: self.tick,
+      recording: None,
+      observers: Observers::default(),
+    }
+  }
 }
 
 pub struct SnakeCtrlState {
@@ -92,55 +149,202 @@ pub struct SnakeCtrlState {
   pub tail_direction: Direction,
 }
 
+/// Public view of one snake in the game.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlayerInfo {
+  pub id: usize,
+  pub score: u64,
+  pub length: usize,
+  pub is_alive: bool,
+  pub direction: Direction,
+}
+
 impl SnakeCtrl {
   pub fn new(opts: &SnakeCtrlOptions) -> SnakeCtrlResult<Self> {
-    let cfg = Rc::new(InnerCfg::from_options(opts));
+    SnakeCtrl::from_cfg(InnerCfg::from_options(opts))
+  }
+
+  pub(crate) fn from_cfg(cfg: InnerCfg) -> SnakeCtrlResult<Self> {
+    let cfg = Arc::new(cfg);
     let board = Board::new(cfg.clone())?;
 
     Ok(SnakeCtrl {
-      next_direction: Direction::Right,
       board,
       cfg,
-      current_direction: Direction::Right,
+      is_over: false,
+      tick: 0,
+      recording: None,
+      observers: Observers::default(),
     })
   }
 
+  /// Queues a turn, one is taken per tick. Turning back from the previous
+  /// queued turn is ignored, unless `fail_on_revert` is set: then the snake
+  /// turns into its own neck and dies on that tick.
   pub fn direction_to(&mut self, direction: Direction) -> SnakeCtrlResult<()> {
-    let is_opposite_direction =
-      self.current_direction.opposite_direction() == direction;
-    if self.cfg.fail_on_revert && is_opposite_direction {
-      return Err(SnakeCtrlErr::SnakeAteItself);
-    }
+    self.direction_to_player(0, direction)
+  }
 
-    if !is_opposite_direction {
-      self.next_direction = direction;
+  /// Same as `direction_to` for the snake of the given player.
+  pub fn direction_to_player(
+    &mut self,
+    id: usize,
+    direction: Direction,
+  ) -> SnakeCtrlResult<()> {
+    let snake = self.board.snake_mut(id)?;
+    if let Some(recording) = &mut self.recording {
+      recording.push(self.tick, id, direction);
     }
-
+    snake.turn(direction, &self.cfg);
     Ok(())
   }
 
   pub fn current_direction(&self) -> Direction {
-    self.current_direction
+    self.board.snakes[0].current_direction
+  }
+
+  pub fn players(&self) -> Vec<PlayerInfo> {
+    self
+      .board
+      .snakes
+      .iter()
+      .map(|s| PlayerInfo {
+        id: s.id,
+        score: s.score,
+        length: s.body.len(),
+        is_alive: s.is_alive,
+        direction: s.current_direction,
+      })
+      .collect()
+  }
+
+  /// Moves every snake one cell. Dying and winning are reported through the
+  /// returned `TickOutcome`, an `Err` means the call itself was wrong, e.g.
+  /// the game is already over.
+  pub fn next_tick(&mut self) -> SnakeCtrlResult<TickOutcome> {
+    if self.is_over {
+      return Err(SnakeCtrlErr::GameIsOver);
+    }
+    let outcome = self.board.move_snakes()?;
+    self.is_over = outcome.is_over();
+    self.tick += 1;
+    notify(self.observers.get_mut(), self.tick, &outcome);
+    Ok(outcome)
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.is_over
+  }
+
+  /// Number of ticks since the game started.
+  pub fn ticks(&self) -> u64 {
+    self.tick
+  }
+
+  /// Progress of the first player towards the goal.
+  pub fn goal_progress(&self, goal: Goal) -> GoalProgress {
+    let snake = &self.board.snakes[0];
+    let (current, target) = match goal {
+      Goal::EatApples(n) => (snake.apples, n),
+      Goal::ReachLength(n) => (snake.body.len() as u64, n as u64),
+      Goal::SurviveTicks(n) => (self.tick, n),
+    };
+    GoalProgress { current, target }
   }
 
-  pub fn next_tick(&mut self) -> SnakeCtrlResult<bool> {
-    self.current_direction = self.next_direction;
-    self.board.move_snake(self.next_direction)
+  /// Whether the first player reached the goal and is still alive.
+  pub fn is_goal_reached(&self, goal: Goal) -> bool {
+    self.board.snakes[0].is_alive && self.goal_progress(goal).is_reached()
   }
 
+  /// Restarts the game. A running recording is dropped, take it first.
   pub fn restart(&mut self) -> SnakeCtrlResult<()> {
-    self.next_direction = Direction::Right;
-    self.current_direction = Direction::Right;
-    self.board.restart()
+    self.reset();
+    self.board.restart()?;
+    self.notify_restart();
+    Ok(())
+  }
+
+  pub fn restart_with_seed(&mut self, seed: u64) -> SnakeCtrlResult<()> {
+    self.reset();
+    self.board.restart_with_seed(seed)?;
+    self.notify_restart();
+    Ok(())
+  }
+
+  fn reset(&mut self) {
+    self.is_over = false;
+    self.tick = 0;
+    self.recording = None;
+  }
+
+  /// Tells the observers about the new game and the food it starts with.
+  fn notify_restart(&mut self) {
+    let seed = self.board.seed();
+    for observer in self.observers.get_mut() {
+      observer.on_restart(seed);
+      for food in &self.board.food {
+        observer.on_food_spawned(food.point);
+      }
+    }
+  }
+
+  /// Registers an observer, it stays through restarts.
+  pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+    self.observers.get_mut().push(observer);
+  }
+
+  /// Drops all the observers.
+  pub fn clear_observers(&mut self) {
+    self.observers.get_mut().clear();
+  }
+
+  /// Starts recording the inputs of the current game. It must be called
+  /// before the first tick to get a replay that plays back exactly.
+  pub fn start_recording(&mut self) {
+    let mut cfg = (*self.cfg).clone();
+    cfg.seed = self.board.seed();
+    self.recording = Some(Replay::new(cfg));
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.recording.is_some()
+  }
+
+  /// Stops recording and returns everything recorded so far.
+  pub fn take_recording(&mut self) -> Option<Replay> {
+    let tick = self.tick;
+    self.recording.take().map(|mut r| {
+      r.set_ticks(tick);
+      r
+    })
+  }
+
+  /// Seed of the current game.
+  pub fn seed(&self) -> u64 {
+    self.board.seed()
+  }
+
+  #[cfg(feature = "serde")]
+  pub fn snapshot(&self) -> SnakeCtrlSnapshot {
+    SnakeCtrlSnapshot::of(self)
+  }
+
+  #[cfg(feature = "serde")]
+  pub fn from_snapshot(snapshot: SnakeCtrlSnapshot) -> SnakeCtrlResult<Self> {
+    snapshot.restore()
   }
 
   pub fn get_state(&self) -> SnakeCtrlState {
     let snake = self.board.clone_snake();
     let food = self.board.clone_food();
-    let head_direction = self.current_direction;
+    let head_direction = self.current_direction();
     let tail = snake.last().unwrap();
     let pre_tail = snake.get(snake.len() - 2).unwrap();
-    let tail_direction = tail.offset_from_near(pre_tail).unwrap();
+    let tail_direction = self
+      .layout()
+      .step_direction(tail, pre_tail)
+      .map_or(head_direction, |d| d.opposite_direction());
 
     SnakeCtrlState {
       snake,
@@ -151,24 +355,44 @@ impl SnakeCtrl {
   }
 
   pub fn get_full_state(&self) -> SnakeCtrlFullState {
-    calc_full_state(
-      &self.board.snake,
-      &self.board.food,
-      self.current_direction,
-      self.cfg.dimension_y,
-      false,
-    )
+    self.full_state_of(&self.board.snakes[0], false)
   }
   pub fn get_full_state_reversed_y(&self) -> SnakeCtrlFullState {
+    self.full_state_of(&self.board.snakes[0], true)
+  }
+
+  /// Full state of the given player's snake, food is shared.
+  pub fn get_player_full_state(
+    &self,
+    id: usize,
+    reverse_y: bool,
+  ) -> SnakeCtrlResult<SnakeCtrlFullState> {
+    Ok(self.full_state_of(self.board.snake(id)?, reverse_y))
+  }
+
+  fn full_state_of(
+    &self,
+    snake: &board::Snake,
+    reverse_y: bool,
+  ) -> SnakeCtrlFullState {
+    let body: Vec<Point> = snake.body.iter().copied().collect();
     calc_full_state(
-      &self.board.snake,
+      &body,
       &self.board.food,
-      self.current_direction,
-      self.cfg.dimension_y,
-      true,
+      snake.current_direction,
+      &self.layout(),
+      reverse_y,
     )
   }
 
+  fn layout(&self) -> Layout<'_> {
+    Layout {
+      dim_x: self.cfg.dimension_x,
+      dim_y: self.cfg.dimension_y,
+      portals: &self.cfg.portals,
+    }
+  }
+
   pub fn get_state_reversed_y(&self) -> SnakeCtrlState {
     let dim_y = self.cfg.dimension_y;
     let mut state = self.get_state();
@@ -177,9 +401,120 @@ impl SnakeCtrl {
     state
   }
 
+  pub fn dimensions(&self) -> (u16, u16) {
+    (self.cfg.dimension_x, self.cfg.dimension_y)
+  }
+
+  /// Obstacle cells of the board.
+  pub fn walls(&self) -> &[Point] {
+    &self.cfg.walls
+  }
+
+  /// Edges the snakes go through.
+  pub fn wrap_edges(&self) -> WrapEdges {
+    self.cfg.wrap()
+  }
+
+  /// Pairs of linked cells.
+  pub fn portals(&self) -> &[(Point, Point)] {
+    &self.cfg.portals
+  }
+
   pub fn get_matrix(&self) -> SnakeCtrlMatrix {
     self.board.get_matrix()
   }
+
+  /// An independent copy to play forward. It shares the RNG state, so it
+  /// gets the same food as this game for the same moves. Recording and
+  /// observers are left behind, like with `clone`.
+  pub fn fork(&self) -> SnakeCtrl {
+    self.clone()
+  }
+
+  /// Plays the moves of the first player, one per tick, on a fork and
+  /// stops early when the game ends. This game is left untouched.
+  pub fn simulate(&self, moves: &[Direction]) -> SnakeCtrlResult<Rollout> {
+    let mut fork = self.fork();
+    let start_score = fork.board.snakes[0].score;
+    let mut rollout = Rollout {
+      ticks: 0,
+      score: 0,
+      length: 0,
+      death: None,
+      is_over: fork.is_over,
+    };
+
+    for direction in moves {
+      if fork.is_over {
+        break;
+      }
+      fork.direction_to(*direction)?;
+      let outcome = fork.next_tick()?;
+      rollout.ticks += 1;
+      if let Some((cause, _)) = outcome.death_of(0) {
+        rollout.death = Some(cause);
+      }
+    }
+
+    let snake = &fork.board.snakes[0];
+    rollout.score = snake.score - start_score;
+    rollout.length = snake.body.len();
+    rollout.is_over = fork.is_over;
+    Ok(rollout)
+  }
+
+  pub fn observe(&self) -> Observation {
+    self.observe_player(0).unwrap()
+  }
+
+  /// What the given player sees, other snakes are obstacles.
+  pub fn observe_player(&self, id: usize) -> SnakeCtrlResult<Observation> {
+    let snake = self.board.snake(id)?;
+    let (poison, food) = self
+      .board
+      .food
+      .iter()
+      .partition::<Vec<&Food>, _>(|f| f.kind.is_deadly());
+    Ok(Observation {
+      snake: snake.body.iter().copied().collect(),
+      others: self.board.others_of(id),
+      walls: self.cfg.walls.clone(),
+      food: food.iter().map(|f| f.point).collect(),
+      poison: poison.iter().map(|f| f.point).collect(),
+      direction: snake.current_direction,
+      dimension_x: self.cfg.dimension_x,
+      dimension_y: self.cfg.dimension_y,
+      wrap: self.cfg.wrap(),
+      portals: self
+        .cfg
+        .portals
+        .iter()
+        .flat_map(|(a, b)| [*a, *b])
+        .collect(),
+      tick: self.tick,
+    })
+  }
+
+  #[cfg(feature = "net")]
+  pub(crate) fn net_frame(&self, round: u64) -> NetFrame {
+    NetFrame {
+      round,
+      tick: self.tick,
+      snakes: self
+        .board
+        .snakes
+        .iter()
+        .map(|s| NetSnake {
+          id: s.id,
+          body: s.body.iter().copied().collect(),
+          direction: s.current_direction,
+          score: s.score,
+          is_alive: s.is_alive,
+        })
+        .collect(),
+      food: self.board.food.clone(),
+    }
+  }
 }
 
 #[cfg(test)]
@@ -195,23 +530,802 @@ mod tests {
     let mut ctrl = SnakeCtrl::new(&opts).unwrap();
     println!("{}\n", ctrl.get_matrix());
     ctrl.direction_to(Direction::Left).unwrap();
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
     ctrl.direction_to(Direction::Left).unwrap();
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
     ctrl.direction_to(Direction::Top).unwrap();
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
     // ctrl.direction_to(Direction::Right);
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
     ctrl.direction_to(Direction::Right).unwrap();
 
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
     ctrl.direction_to(Direction::Left).unwrap();
-    ctrl.next_tick();
+    ctrl.next_tick().unwrap();
     println!("{}\n", ctrl.get_matrix());
   }
+
+  #[test]
+  fn matrix_cells_and_exports() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(5)
+      .dimension_y(3)
+      .walls(vec![Point(0, 2)])
+      .food(vec![Point(4, 1)]);
+    let matrix = SnakeCtrl::new(&opts).unwrap().get_matrix();
+    assert_eq!(matrix.get(2, 1).unwrap(), Cell::Head);
+    assert_eq!(matrix.get(1, 1).unwrap(), Cell::Body);
+    assert_eq!(matrix.get(4, 1).unwrap(), Cell::Food(FoodKind::Apple));
+    assert!(matches!(
+      matrix.get(5, 0),
+      Err(SnakeCtrlErr::ColumnIndexOutOfBounds(5))
+    ));
+    assert!(matches!(
+      matrix.get(0, 3),
+      Err(SnakeCtrlErr::RowIndexOutOfBounds(3))
+    ));
+    assert_eq!(matrix.cell_rows().count(), 3);
+    assert_eq!(matrix.cells().filter(|(_, c)| c.is_snake()).count(), 3);
+    assert_eq!(matrix.to_f32()[7], Cell::Head.index() as f32);
+    let planes = matrix.one_hot();
+    assert_eq!(planes.len(), Cell::COUNT * 15);
+    assert_eq!(planes.iter().sum::<f32>(), 15.0);
+    assert_eq!(planes[Cell::Wall.index() * 15 + 10], 1.0);
+    assert_eq!(matrix.render(&Glyphs::ascii()), "#....\n+o@.A\n.....");
+    assert_eq!(matrix.rows()[1], vec![1, 1, 1, 0, 7]);
+    assert_eq!(matrix.to_string(), "0 0 0 0 0\n1 1 1 0 7\n8 0 0 0 0");
+  }
+
+  fn food_sequence(ctrl: &mut SnakeCtrl, ticks: usize) -> Vec<Point> {
+    let mut food = ctrl.get_state().food;
+    for i in 0..ticks {
+      if i % 5 == 0 {
+        ctrl.direction_to(Direction::Top).unwrap();
+      } else if i % 5 == 2 {
+        ctrl.direction_to(Direction::Right).unwrap();
+      }
+      ctrl.next_tick().unwrap();
+      food.extend(ctrl.get_state().food);
+    }
+    food
+  }
+
+  #[test]
+  fn same_seed_same_food() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .seed(42);
+    let mut a = SnakeCtrl::new(&opts).unwrap();
+    let mut b = SnakeCtrl::new(&opts).unwrap();
+    assert_eq!(food_sequence(&mut a, 60), food_sequence(&mut b, 60));
+
+    a.restart().unwrap();
+    b.restart().unwrap();
+    assert_eq!(a.seed(), 43);
+    assert_eq!(food_sequence(&mut a, 60), food_sequence(&mut b, 60));
+
+    a.restart_with_seed(42).unwrap();
+    let mut c = SnakeCtrl::new(&opts).unwrap();
+    assert_eq!(food_sequence(&mut a, 60), food_sequence(&mut c, 60));
+  }
+
+  #[test]
+  fn fills_the_board() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(6)
+      .dimension_y(1)
+      .seed(1);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+
+    let mut outcome = TickOutcome::default();
+    for _ in 0..100 {
+      outcome = ctrl.next_tick().unwrap();
+      if outcome.is_over() {
+        break;
+      }
+    }
+    assert!(outcome.is_won());
+    assert_eq!(ctrl.get_state().snake.len(), 6);
+    assert!(ctrl.get_state().food.is_empty());
+    assert!(matches!(ctrl.next_tick(), Err(SnakeCtrlErr::GameIsOver)));
+  }
+
+  #[test]
+  fn fills_the_board_around_portals() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(8)
+      .dimension_y(1)
+      .seed(1)
+      .portals(vec![(Point(6, 0), Point(7, 0))]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+
+    let mut outcome = TickOutcome::default();
+    for _ in 0..200 {
+      outcome = ctrl.next_tick().unwrap();
+      if outcome.is_over() {
+        break;
+      }
+    }
+    assert!(outcome.is_won());
+    assert!(ctrl.get_state().food.is_empty());
+  }
+
+  #[test]
+  fn reports_tick_events() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(6)
+      .dimension_y(4)
+      .walking_through_the_walls(false)
+      .auto_gen_food(false);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    let before = ctrl.get_state().snake;
+
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(
+      outcome.events,
+      vec![TickEvent::Moved {
+        snake: 0,
+        from: Point(3, 2),
+        to: Point(4, 2)
+      }]
+    );
+
+    ctrl.next_tick().unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(outcome.death(), Some((DeathCause::HitTheWall, Point(5, 2))));
+    assert_eq!(ctrl.get_state().snake.len(), before.len());
+    assert_eq!(ctrl.get_state().snake[0], Point(5, 2));
+    assert!(ctrl.is_over());
+
+    ctrl.restart().unwrap();
+    assert!(!ctrl.is_over());
+    assert_eq!(ctrl.get_state().snake, before);
+  }
+
+  #[test]
+  fn revert_kills_when_configured() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .fail_on_revert(true);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.direction_to(Direction::Left).unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(outcome.death(), Some((DeathCause::AteItself, Point(3, 4))));
+  }
+
+  #[test]
+  fn snakes_collide() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(5)
+      .walking_through_the_walls(false)
+      .auto_gen_food(false)
+      .players(2);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    assert_eq!(ctrl.get_player_full_state(1, false).unwrap().snake.len(), 3);
+    ctrl.direction_to_player(0, Direction::Top).unwrap();
+    ctrl.direction_to_player(1, Direction::Bottom).unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    let head_to_head = Some((DeathCause::HeadToHead, Point(4, 2)));
+    assert_eq!(outcome.death_of(0), head_to_head);
+    assert_eq!(outcome.death_of(1), head_to_head);
+    assert_eq!(outcome.winner(), None);
+    assert!(ctrl.is_over());
+
+    let opts = opts.dimension_y(4).death_rule(DeathRule::Remove);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.direction_to_player(0, Direction::Top).unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(
+      outcome.death_of(0),
+      Some((DeathCause::HitSnake, Point(4, 2)))
+    );
+    assert_eq!(outcome.winner(), Some(1));
+    assert!(!ctrl.players()[0].is_alive);
+    assert!(matches!(
+      ctrl.direction_to_player(2, Direction::Top),
+      Err(SnakeCtrlErr::UnknownPlayer(2))
+    ));
+  }
+
+  /// Runs a one row board until the snake eats the only food.
+  fn eat_one(spawn: FoodSpawn, size: u16) -> (SnakeCtrl, TickOutcome) {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(1)
+      .initial_snake_size(size)
+      .food_spawns(vec![spawn]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    let kind = ctrl.get_full_state().food[0].kind;
+    assert_eq!(kind, spawn.kind);
+    assert_eq!(
+      ctrl
+        .get_matrix()
+        .cells()
+        .filter(|(_, c)| matches!(c, Cell::Food(_)))
+        .count(),
+      1
+    );
+    loop {
+      let outcome = ctrl.next_tick().unwrap();
+      if outcome.eaten_kind_by(0).is_some() || outcome.is_over() {
+        return (ctrl, outcome);
+      }
+    }
+  }
+
+  #[test]
+  fn food_kinds_have_effects() {
+    let (ctrl, outcome) = eat_one(FoodSpawn::new(FoodKind::Golden, 1), 3);
+    assert_eq!(outcome.eaten_kind_by(0), Some(FoodKind::Golden));
+    assert_eq!(ctrl.players()[0].score, 5);
+    assert_eq!(ctrl.players()[0].length, 4);
+    assert!(!ctrl.is_goal_reached(Goal::EatApples(1)));
+
+    let (ctrl, outcome) = eat_one(FoodSpawn::new(FoodKind::Shrink, 1), 4);
+    let shrank = TickEvent::Shrank {
+      snake: 0,
+      length: 2,
+    };
+    assert!(outcome.events.contains(&shrank));
+    assert_eq!(ctrl.get_state().snake.len(), 2);
+
+    let (ctrl, outcome) = eat_one(FoodSpawn::new(FoodKind::Poison, 1), 3);
+    assert_eq!(outcome.death_of(0).map(|d| d.0), Some(DeathCause::Poisoned));
+    assert!(ctrl.is_over());
+
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .food_spawns(vec![FoodSpawn::new(FoodKind::Apple, 1).lifetime(1)]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    let food = ctrl.get_state().food[0];
+    let outcome = ctrl.next_tick().unwrap();
+    let expired = outcome.events.contains(&TickEvent::FoodExpired(food));
+    assert!(expired != outcome.is_ate());
+    assert_eq!(ctrl.get_state().food.len(), 1);
+  }
+
+  #[test]
+  fn grows_over_several_ticks() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(12)
+      .dimension_y(1)
+      .auto_gen_food(false)
+      .food(vec![Point(7, 0)])
+      .growth_per_food(3);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    let lengths: Vec<usize> = (0..4)
+      .map(|_| {
+        ctrl.next_tick().unwrap();
+        ctrl.players()[0].length
+      })
+      .collect();
+    assert_eq!(lengths, vec![4, 5, 6, 6]);
+  }
+
+  #[test]
+  fn walls_block_snake_and_food() {
+    let walls: Vec<Point> = (0..7).map(|y| Point(6, y)).collect();
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(7)
+      .walls(walls.clone())
+      .seed(4);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    assert_eq!(ctrl.get_matrix().get(6, 3).unwrap(), Cell::Wall);
+    assert!(ctrl.get_state().food.iter().all(|f| !walls.contains(f)));
+
+    ctrl.next_tick().unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(
+      outcome.death(),
+      Some((DeathCause::HitObstacle, Point(6, 3)))
+    );
+
+    let opts = opts.walls(vec![Point(3, 3)]);
+    assert!(matches!(
+      SnakeCtrl::new(&opts),
+      Err(SnakeCtrlErr::SpawnBlocked)
+    ));
+  }
+
+  #[test]
+  fn rejects_blocked_food() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .walls(vec![Point(0, 0)])
+      .portals(vec![(Point(8, 8), Point(8, 0))]);
+    let blocked = |food: Point| {
+      let opts = opts.clone().food(vec![Point(1, 1), food]);
+      match SnakeCtrl::new(&opts) {
+        Err(SnakeCtrlErr::FoodBlocked(p)) => p == food,
+        _ => false,
+      }
+    };
+    assert!(blocked(Point(0, 0)));
+    assert!(blocked(Point(8, 0)));
+    assert!(blocked(Point(3, 4)));
+    assert!(blocked(Point(1, 1)));
+    assert!(matches!(
+      SnakeCtrl::new(&opts.clone().food(vec![Point(9, 1)])),
+      Err(SnakeCtrlErr::ColumnIndexOutOfBounds(9))
+    ));
+    assert!(SnakeCtrl::new(&opts.food(vec![Point(1, 1), Point(2, 2)])).is_ok());
+  }
+
+  #[test]
+  fn clones_play_on_their_own() {
+    fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<SnakeCtrl>();
+
+    // Observers only have to be `Send`.
+    struct Ticks(std::cell::Cell<u64>);
+    impl Observer for Ticks {
+      fn on_tick(&mut self, tick: u64, _: &TickOutcome) {
+        self.0.set(tick);
+      }
+    }
+
+    let opts = SnakeCtrlOptions::default().dimension_x(9).dimension_y(9);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.add_observer(Box::new(Ticks(Default::default())));
+    ctrl.start_recording();
+    assert!(!ctrl.clone().is_recording());
+    let mut other = ctrl.clone();
+    other.direction_to(Direction::Top).unwrap();
+    other.next_tick().unwrap();
+    ctrl.next_tick().unwrap();
+    assert_eq!(ctrl.get_state().snake[0], Point(5, 4));
+    assert_eq!(other.get_state().snake[0], Point(4, 5));
+
+    let handle = std::thread::spawn(move || {
+      ctrl.next_tick().unwrap();
+      ctrl
+    });
+    assert_eq!(handle.join().unwrap().ticks(), 2);
+  }
+
+  #[test]
+  fn simulates_on_a_fork() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .walking_through_the_walls(false)
+      .food(vec![Point(6, 4)]);
+    let ctrl = SnakeCtrl::new(&opts).unwrap();
+    let rollout = ctrl.simulate(&[Direction::Right; 2]).unwrap();
+    assert_eq!((rollout.ticks, rollout.score, rollout.length), (2, 1, 4));
+    assert!(!rollout.is_over);
+    let rollout = ctrl.simulate(&[Direction::Top; 9]).unwrap();
+    assert_eq!(rollout.ticks, 5);
+    assert_eq!(rollout.death, Some(DeathCause::HitTheWall));
+    assert_eq!(ctrl.ticks(), 0);
+    assert_eq!(ctrl.get_state().snake[0], Point(4, 4));
+
+    let mut fork = ctrl.fork();
+    fork.next_tick().unwrap();
+    assert_eq!(fork.ticks(), 1);
+    assert_eq!(ctrl.ticks(), 0);
+  }
+
+  #[test]
+  fn env_steps_with_rewards() {
+    use crate::env::{Action, Env, GridPlanes, Rewards};
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .walking_through_the_walls(false)
+      .food(vec![Point(6, 4)]);
+    let rewards = Rewards {
+      step: -0.01,
+      approach: 0.1,
+      ..Rewards::default()
+    };
+    let mut env = Env::new(&opts).unwrap().rewards(rewards);
+    let obs = env.reset(3).unwrap();
+    assert_eq!(
+      obs,
+      vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]
+    );
+
+    let (_, reward, done, _) = env.step(Action::Straight).unwrap();
+    assert!((reward - 0.09).abs() < 1e-6 && !done);
+    let (_, reward, _, info) = env.step(Action::Straight).unwrap();
+    assert!((reward - 0.99).abs() < 1e-6);
+    assert!(info.ate);
+    assert_eq!((info.score, info.length), (1, 4));
+    env.step(Action::Left).unwrap();
+    assert_eq!(env.game().current_direction(), Direction::Top);
+    let mut last = env.step(Action::Straight).unwrap();
+    while !last.2 {
+      last = env.step(Action::Straight).unwrap();
+    }
+    let (_, reward, _, info) = last;
+    assert_eq!(info.death, Some(DeathCause::HitTheWall));
+    assert!((reward + 1.01).abs() < 1e-6);
+    assert!(!info.truncated);
+    assert!(env.step(Action::Straight).is_err());
+
+    let mut env = Env::new(&opts)
+      .unwrap()
+      .encoder(Box::new(GridPlanes))
+      .max_ticks(2);
+    assert_eq!(env.observation_size(), Cell::COUNT * 81);
+    assert_eq!(env.reset(3).unwrap().len(), Cell::COUNT * 81);
+    env.step(Action::Right).unwrap();
+    let (_, _, done, info) = env.step(Action::Right).unwrap();
+    assert!(done && info.truncated);
+  }
+
+  #[test]
+  fn notifies_observers() {
+    #[derive(Default)]
+    struct Counts {
+      eaten: usize,
+      spawned: usize,
+      deaths: usize,
+      wraps: usize,
+      restarts: usize,
+      ticks: u64,
+    }
+    use std::sync::Mutex;
+    struct Counter(Arc<Mutex<Counts>>);
+    impl Observer for Counter {
+      fn on_food_eaten(&mut self, _: usize, _: Point, _: FoodKind) {
+        self.0.lock().unwrap().eaten += 1;
+      }
+      fn on_food_spawned(&mut self, _: Point) {
+        self.0.lock().unwrap().spawned += 1;
+      }
+      fn on_death(&mut self, _: usize, _: DeathCause, _: Point) {
+        self.0.lock().unwrap().deaths += 1;
+      }
+      fn on_wrap(&mut self, _: usize, _: Point, _: Point) {
+        self.0.lock().unwrap().wraps += 1;
+      }
+      fn on_restart(&mut self, _: u64) {
+        self.0.lock().unwrap().restarts += 1;
+      }
+      fn on_tick(&mut self, tick: u64, _: &TickOutcome) {
+        self.0.lock().unwrap().ticks = tick;
+      }
+    }
+
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(1)
+      .fail_on_revert(true)
+      .seed(1)
+      .food(vec![Point(6, 0)]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    let counts = Arc::new(Mutex::new(Counts::default()));
+    ctrl.add_observer(Box::new(Counter(counts.clone())));
+    for _ in 0..5 {
+      ctrl.next_tick().unwrap();
+    }
+    ctrl.direction_to(Direction::Left).unwrap();
+    ctrl.next_tick().unwrap();
+    ctrl.restart().unwrap();
+    ctrl.restart_with_seed(5).unwrap();
+    let counts = counts.lock().unwrap();
+    assert_eq!((counts.eaten, counts.spawned, counts.deaths), (1, 3, 1));
+    assert_eq!((counts.wraps, counts.restarts, counts.ticks), (1, 2, 6));
+  }
+
+  #[test]
+  fn queues_turns() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .auto_gen_food(false);
+    let heads = |opts: &SnakeCtrlOptions| {
+      let mut ctrl = SnakeCtrl::new(opts).unwrap();
+      ctrl.direction_to(Direction::Top).unwrap();
+      ctrl.direction_to(Direction::Left).unwrap();
+      ctrl.direction_to(Direction::Bottom).unwrap();
+      (0..3)
+        .map(|_| {
+          ctrl.next_tick().unwrap();
+          ctrl.get_state().snake[0]
+        })
+        .collect::<Vec<Point>>()
+    };
+    assert_eq!(heads(&opts), vec![Point(4, 3), Point(4, 2), Point(4, 1)]);
+    let queued = opts.input_queue_depth(2);
+    assert_eq!(heads(&queued), vec![Point(4, 5), Point(3, 5), Point(2, 5)]);
+  }
+
+  #[test]
+  fn full_queue_keeps_its_last_turn() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .input_queue_depth(3);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    for d in [
+      Direction::Top,
+      Direction::Left,
+      Direction::Bottom,
+      Direction::Left,
+    ] {
+      ctrl.direction_to(d).unwrap();
+    }
+    for d in [Direction::Top, Direction::Left, Direction::Bottom] {
+      ctrl.next_tick().unwrap();
+      assert_eq!(ctrl.current_direction(), d);
+    }
+  }
+
+  #[test]
+  fn starts_with_custom_body() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(5)
+      .dimension_y(5)
+      .auto_gen_food(false)
+      .start_head(Point(2, 2))
+      .start_direction(Direction::Top)
+      .start_body(vec![Point(2, 1), Point(1, 1), Point(1, 2)]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.next_tick().unwrap();
+    let snake = ctrl.get_state().snake;
+    assert_eq!(
+      snake,
+      vec![Point(2, 3), Point(2, 2), Point(2, 1), Point(1, 1)]
+    );
+    ctrl.restart().unwrap();
+    assert_eq!(ctrl.get_state().snake[3], Point(1, 2));
+
+    let broken = opts.clone().start_body(vec![Point(2, 0)]);
+    assert!(matches!(
+      SnakeCtrl::new(&broken),
+      Err(SnakeCtrlErr::BrokenSnake(0))
+    ));
+    let backwards = opts.clone().start_direction(Direction::Bottom);
+    assert!(matches!(
+      SnakeCtrl::new(&backwards),
+      Err(SnakeCtrlErr::BrokenSnake(0))
+    ));
+    let outside = opts.clone().start_body(vec![Point(2, 1), Point(2, 5)]);
+    assert!(matches!(
+      SnakeCtrl::new(&outside),
+      Err(SnakeCtrlErr::RowIndexOutOfBounds(5))
+    ));
+    let on_wall = opts.clone().walls(vec![Point(1, 1)]);
+    assert!(matches!(
+      SnakeCtrl::new(&on_wall),
+      Err(SnakeCtrlErr::SpawnBlocked)
+    ));
+    let start = SnakeStart::new(Point(3, 1), Direction::Right);
+    let crossed = opts
+      .players(2)
+      .starts(vec![start.clone(), start.body(vec![Point(3, 0)])]);
+    assert!(matches!(
+      SnakeCtrl::new(&crossed),
+      Err(SnakeCtrlErr::SpawnBlocked)
+    ));
+  }
+
+  #[test]
+  fn portals_and_wrap_edges() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(3)
+      .auto_gen_food(false)
+      .wrap_edges(WrapEdges::horizontal())
+      .start_head(Point(3, 1))
+      .portals(vec![(Point(5, 1), Point(7, 2))]);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.next_tick().unwrap();
+    assert!(ctrl.next_tick().unwrap().is_teleported());
+    assert_eq!(ctrl.get_state().snake[0], Point(7, 2));
+    let parts = ctrl.get_full_state().snake;
+    assert!(matches!(parts[1].variant, SnakePartVariant::Body(false)));
+    assert!(matches!(
+      parts[2].variant,
+      SnakePartVariant::Tail(Direction::Left)
+    ));
+
+    ctrl.next_tick().unwrap();
+    assert!(ctrl.next_tick().unwrap().is_teleported());
+    assert_eq!(ctrl.get_state().snake[0], Point(0, 2));
+    ctrl.direction_to(Direction::Bottom).unwrap();
+    ctrl.next_tick().unwrap();
+    let parts = ctrl.get_full_state().snake;
+    assert!(matches!(parts[1].variant, SnakePartVariant::Corner(_)));
+    ctrl.next_tick().unwrap();
+    let outcome = ctrl.next_tick().unwrap();
+    assert_eq!(outcome.death().map(|d| d.0), Some(DeathCause::HitTheWall));
+
+    let bad = opts.clone().walls(vec![Point(7, 2)]);
+    assert!(matches!(
+      SnakeCtrl::new(&bad),
+      Err(SnakeCtrlErr::BadPortal(Point(7, 2)))
+    ));
+
+    let src = "---\n1..\n..S\n.1.\n";
+    let level: level::Level = src.parse().unwrap();
+    let ctrl = level.new_ctrl().unwrap();
+    assert_eq!(ctrl.portals(), &[(Point(0, 2), Point(1, 0))]);
+    let err = level::Level::parse("---\nS1.\n").err().unwrap();
+    assert_eq!(err.kind, level::LevelErrorKind::UnpairedPortal('1'));
+  }
+
+  #[test]
+  fn loads_level() {
+    let src = "name: Test\nwrap: false\ndirection: up\ngoal: apples 1\n---\n\
+               #######\n\
+               #  A  #\n\
+               #  S  #\n\
+               #     #\n\
+               #     #\n\
+               #######\n";
+    let level: level::Level = src.parse().unwrap();
+    assert_eq!(level.name(), Some("Test"));
+    assert_eq!(level.dimensions(), (7, 6));
+    let goal = level.goal().unwrap();
+    assert_eq!(goal, Goal::EatApples(1));
+    let mut ctrl = level.new_ctrl().unwrap();
+    assert!(!ctrl.is_goal_reached(goal));
+    assert_eq!(ctrl.get_state().snake[0], Point(3, 3));
+    assert_eq!(ctrl.get_state().food, vec![Point(3, 4)]);
+    assert_eq!(ctrl.get_matrix().get(0, 0).unwrap(), Cell::Wall);
+    assert_eq!(ctrl.next_tick().unwrap().eaten(), Some(Point(3, 4)));
+    assert!(ctrl.is_goal_reached(goal));
+    let progress = ctrl.goal_progress(Goal::SurviveTicks(5));
+    assert_eq!((progress.current, progress.target), (1, 5));
+
+    let err = level::Level::parse("---\n#S#\n#?#\n").err().unwrap();
+    assert_eq!((err.line, err.column), (3, 2));
+    let err = level::Level::parse("wrap: maybe\n---\nS\n").err().unwrap();
+    assert_eq!((err.line, err.column), (1, 7));
+    let err = level::Level::parse(" wrap :  wrap\n---\nS\n")
+      .err()
+      .unwrap();
+    assert_eq!((err.line, err.column), (1, 10));
+    let err = level::Level::parse("goal: 5 apples\n---\nS\n")
+      .err()
+      .unwrap();
+    assert_eq!((err.line, err.column), (1, 7));
+    let err = level::Level::parse("---\n#S#\n##\n").err().unwrap();
+    assert!(matches!(err.kind, level::LevelErrorKind::RaggedRow { .. }));
+  }
+
+  #[test]
+  fn replays_recorded_game() {
+    let opts = SnakeCtrlOptions::default().dimension_x(9).dimension_y(9);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    ctrl.start_recording();
+    let food = food_sequence(&mut ctrl, 40);
+    let replay = ctrl.take_recording().unwrap();
+    assert_eq!(replay.ticks(), 40);
+    assert_eq!(replay.seed(), ctrl.seed());
+
+    let mut replayed = replay.new_ctrl().unwrap();
+    let mut player = ReplayPlayer::new(replay);
+    let mut replayed_food = replayed.get_state().food;
+    while !player.is_finished(&replayed) {
+      player.step(&mut replayed).unwrap();
+      replayed_food.extend(replayed.get_state().food);
+    }
+    assert_eq!(replayed_food, food);
+    assert_eq!(replayed.get_state().snake, ctrl.get_state().snake);
+  }
+
+  #[test]
+  fn simulates_in_parallel() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(10)
+      .dimension_y(10)
+      .walking_through_the_walls(false);
+    let sim = Simulator::new(&opts)
+      .games(40)
+      .threads(4)
+      .max_ticks(2_000)
+      .seed(3);
+    let turn_at_wall = |_: usize| -> Box<dyn Policy> {
+      Box::new(|obs: &Observation| {
+        let Point(x, y) = obs.head();
+        match obs.direction {
+          Direction::Right if x + 1 == obs.dimension_x => Direction::Top,
+          Direction::Top if y + 1 == obs.dimension_y => Direction::Left,
+          Direction::Left if x == 0 => Direction::Bottom,
+          Direction::Bottom if y == 0 => Direction::Right,
+          d => d,
+        }
+      })
+    };
+
+    let stats = sim.run(turn_at_wall).unwrap();
+    assert_eq!(stats.len(), 40);
+    assert_eq!(stats[7].seed, 10);
+    assert_eq!(stats, sim.run(turn_at_wall).unwrap());
+
+    let straight = sim.run(|_| Box::new(|o: &Observation| o.direction));
+    let summary = SimSummary::from_stats(&straight.unwrap());
+    assert_eq!(summary.hit_the_wall, 40);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn snapshot_roundtrip() {
+    let opts = SnakeCtrlOptions::default()
+      .dimension_x(9)
+      .dimension_y(9)
+      .seed(5);
+    let mut ctrl = SnakeCtrl::new(&opts).unwrap();
+    food_sequence(&mut ctrl, 12);
+    ctrl.direction_to(Direction::Left).unwrap();
+
+    let json = serde_json::to_string(&ctrl.snapshot()).unwrap();
+    let snapshot: SnakeCtrlSnapshot = serde_json::from_str(&json).unwrap();
+    let mut restored = SnakeCtrl::from_snapshot(snapshot).unwrap();
+
+    assert_eq!(restored.get_state().snake, ctrl.get_state().snake);
+    assert_eq!(restored.current_direction(), ctrl.current_direction());
+    assert_eq!(
+      food_sequence(&mut restored, 60),
+      food_sequence(&mut ctrl, 60)
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn rejects_broken_snapshots() {
+    use serde_json::{json, Value};
+
+    let opts = SnakeCtrlOptions::default().players(2);
+    let saved = serde_json::to_value(SnakeCtrl::new(&opts).unwrap().snapshot());
+    let load = |edit: &dyn Fn(&mut Value)| {
+      let mut json = saved.as_ref().unwrap().clone();
+      edit(&mut json);
+      SnakeCtrl::from_snapshot(serde_json::from_value(json).unwrap())
+    };
+
+    assert!(matches!(
+      load(&|j| j["version"] = json!(0)),
+      Err(SnakeCtrlErr::SnapshotVersion { found: 0, .. })
+    ));
+    assert!(matches!(
+      load(&|j| {
+        let head = j["snakes"][0]["body"][0].clone();
+        j["snakes"][1]["body"][0] = head;
+      }),
+      Err(SnakeCtrlErr::SnakeBlocked(1))
+    ));
+    assert!(matches!(
+      load(&|j| j["cfg"]["walls"] = json!([j["snakes"][0]["body"][0]])),
+      Err(SnakeCtrlErr::SnakeBlocked(0))
+    ));
+    assert!(matches!(
+      load(&|j| j["food"][0]["point"] = j["snakes"][1]["body"][0].clone()),
+      Err(SnakeCtrlErr::FoodBlocked(_))
+    ));
+    assert!(matches!(
+      load(&|j| j["cfg"]["portals"] = json!([[[0, 0], [0, 0]]])),
+      Err(SnakeCtrlErr::BadPortal(Point(0, 0)))
+    ));
+
+    let mut ctrl = load(&|j| {
+      j["cfg"]["initial_snake_size"] = json!(1);
+      j["cfg"]["players"] = json!(0);
+    })
+    .unwrap();
+    ctrl.restart().unwrap();
+    assert_eq!(ctrl.get_state().snake.len(), 3);
+    assert!(ctrl.observe_player(1).is_err());
+
+    let mut ctrl = load(&|j| j["cfg"]["input_queue_depth"] = json!(0)).unwrap();
+    ctrl.direction_to(Direction::Top).unwrap();
+    ctrl.next_tick().unwrap();
+    assert_eq!(ctrl.current_direction(), Direction::Top);
+  }
 }
@@ -1,51 +1,245 @@
-use crate::Point;
+use crate::err::{SnakeCtrlErr, SnakeCtrlResult};
+use crate::{Food, FoodKind, Point};
 use std::fmt;
 
+/// What lies on a cell of the board.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Cell {
+  #[default]
+  Empty,
+  Head,
+  Body,
+  Tail,
+  Food(FoodKind),
+  Wall,
+}
+
+impl Cell {
+  /// Number of distinct cells, the planes of `Matrix::one_hot`.
+  pub const COUNT: usize = 5 + FoodKind::ALL.len();
+
+  /// Position of the cell among all the cells: empty, head, body, tail,
+  /// wall, then the food in the order of `FoodKind::ALL`.
+  pub fn index(&self) -> usize {
+    match self {
+      Cell::Empty => 0,
+      Cell::Head => 1,
+      Cell::Body => 2,
+      Cell::Tail => 3,
+      Cell::Wall => 4,
+      Cell::Food(kind) => 5 + *kind as usize,
+    }
+  }
+
+  /// Code of the C API: 0 empty, 1 snake, 7 apple, 8 wall, 9 golden apple,
+  /// 10 shrink, 11 speed up, 12 slow down, 13 poison.
+  pub fn code(&self) -> u8 {
+    match self {
+      Cell::Empty => 0,
+      Cell::Head | Cell::Body | Cell::Tail => 1,
+      Cell::Wall => 8,
+      Cell::Food(FoodKind::Apple) => 7,
+      Cell::Food(kind) => 8 + *kind as u8,
+    }
+  }
+
+  pub fn is_snake(&self) -> bool {
+    matches!(self, Cell::Head | Cell::Body | Cell::Tail)
+  }
+}
+
+/// Characters `Matrix::render` draws the cells with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Glyphs {
+  pub empty: char,
+  pub head: char,
+  pub body: char,
+  pub tail: char,
+  pub wall: char,
+  /// One per food kind, in the order of `FoodKind::ALL`.
+  pub food: [char; FoodKind::ALL.len()],
+}
+
+impl Glyphs {
+  pub fn unicode() -> Self {
+    Glyphs {
+      empty: '·',
+      head: '◉',
+      body: '●',
+      tail: '•',
+      wall: '█',
+      food: ['◆', '★', '▼', '»', '«', '☠'],
+    }
+  }
+
+  /// The characters of the level format where it has them.
+  pub fn ascii() -> Self {
+    Glyphs {
+      empty: '.',
+      head: '@',
+      body: 'o',
+      tail: '+',
+      wall: '#',
+      food: ['A', 'G', 's', '>', '<', 'x'],
+    }
+  }
+
+  pub fn of(&self, cell: Cell) -> char {
+    match cell {
+      Cell::Empty => self.empty,
+      Cell::Head => self.head,
+      Cell::Body => self.body,
+      Cell::Tail => self.tail,
+      Cell::Wall => self.wall,
+      Cell::Food(kind) => self.food[kind as usize],
+    }
+  }
+}
+
+impl Default for Glyphs {
+  fn default() -> Self {
+    Glyphs::unicode()
+  }
+}
+
+/// The board cell by cell, row after row from `y = 0`.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Matrix {
-  pub rows: Vec<Vec<u8>>,
+  dim_x: u16,
+  dim_y: u16,
+  cells: Vec<Cell>,
 }
 
 impl Matrix {
   pub fn new(dim_x: u16, dim_y: u16) -> Self {
-    let mut rows = Vec::with_capacity(dim_y as usize);
-    for _ in 0..dim_y {
-      let mut cells = Vec::with_capacity(dim_x as usize);
-      for _ in 0..dim_x {
-        cells.push(0);
-      }
-      rows.push(cells)
+    Matrix {
+      dim_x,
+      dim_y,
+      cells: vec![Cell::Empty; usize::from(dim_x) * usize::from(dim_y)],
     }
+  }
+
+  pub fn dimensions(&self) -> (u16, u16) {
+    (self.dim_x, self.dim_y)
+  }
+
+  pub fn get(&self, x: u16, y: u16) -> SnakeCtrlResult<Cell> {
+    if x >= self.dim_x {
+      return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(x));
+    }
+    if y >= self.dim_y {
+      return Err(SnakeCtrlErr::RowIndexOutOfBounds(y));
+    }
+    Ok(self.cells[self.index(Point(x, y))])
+  }
 
-    Matrix { rows }
+  fn index(&self, p: Point) -> usize {
+    usize::from(p.1) * usize::from(self.dim_x) + usize::from(p.0)
+  }
+
+  fn set(&mut self, p: Point, cell: Cell) {
+    let ind = self.index(p);
+    self.cells[ind] = cell;
+  }
+
+  /// Rows from `y = 0` up.
+  pub fn cell_rows(&self) -> impl DoubleEndedIterator<Item = &[Cell]> {
+    self.cells.chunks(usize::from(self.dim_x).max(1))
+  }
+
+  /// `Cell::code` of every cell, one `Vec` per row from `y = 0` up. This is
+  /// what the `rows` field held before the cells got typed.
+  pub fn rows(&self) -> Vec<Vec<u8>> {
+    self
+      .cell_rows()
+      .map(|row| row.iter().map(Cell::code).collect())
+      .collect()
+  }
+
+  /// Every cell with its point, row after row.
+  pub fn cells(&self) -> impl Iterator<Item = (Point, Cell)> + '_ {
+    let dim_x = usize::from(self.dim_x);
+    self.cells.iter().enumerate().map(move |(ind, cell)| {
+      (Point((ind % dim_x) as u16, (ind / dim_x) as u16), *cell)
+    })
+  }
+
+  /// `Cell::code` of every cell, row after row.
+  pub fn to_codes(&self) -> Vec<u8> {
+    self.cells.iter().map(Cell::code).collect()
+  }
+
+  /// `Cell::index` of every cell as a float, row after row.
+  pub fn to_f32(&self) -> Vec<f32> {
+    self.cells.iter().map(|c| c.index() as f32).collect()
+  }
+
+  /// `Cell::COUNT` planes of `dim_x * dim_y` values one after another,
+  /// plane `i` has 1.0 where the cell index is `i` and 0.0 elsewhere.
+  pub fn one_hot(&self) -> Vec<f32> {
+    let len = self.cells.len();
+    let mut planes = vec![0.0; Cell::COUNT * len];
+    for (ind, cell) in self.cells.iter().enumerate() {
+      planes[cell.index() * len + ind] = 1.0;
+    }
+    planes
+  }
+
+  /// One line per row with the highest `y` on top, like in the game and
+  /// the level files.
+  pub fn render(&self, glyphs: &Glyphs) -> String {
+    let lines: Vec<String> = self
+      .cell_rows()
+      .rev()
+      .map(|row| row.iter().map(|c| glyphs.of(*c)).collect())
+      .collect();
+    lines.join("\n")
+  }
+
+  pub(crate) fn add_snake<'a, I: IntoIterator<Item = &'a Point>>(
+    &mut self,
+    snake: I,
+  ) {
+    let mut points = snake.into_iter().peekable();
+    let mut is_head = true;
+    while let Some(p) = points.next() {
+      let cell = if is_head {
+        Cell::Head
+      } else if points.peek().is_none() {
+        Cell::Tail
+      } else {
+        Cell::Body
+      };
+      self.set(*p, cell);
+      is_head = false;
+    }
   }
 
-  pub(crate) fn add_snake(&mut self, snake: &[Point]) {
-    for point in snake {
-      self.rows[point.1 as usize][point.0 as usize] = 1;
+  pub(crate) fn add_walls(&mut self, walls: &[Point]) {
+    for w in walls {
+      self.set(*w, Cell::Wall);
     }
   }
 
-  pub(crate) fn add_food(&mut self, food: &[Point]) {
+  pub(crate) fn add_food(&mut self, food: &[Food]) {
     for f in food {
-      self.rows[f.1 as usize][f.0 as usize] = 7;
+      self.set(f.point, Cell::Food(f.kind));
     }
   }
 }
 
+/// The codes of `rows`, separated by spaces, one line per row from `y = 0`.
+/// `render` draws the board instead.
 impl fmt::Display for Matrix {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let result = self
-      .rows
+    let lines: Vec<String> = self
+      .rows()
       .iter()
-      .map(|cells| {
-        cells
-          .iter()
-          .map(|s| s.to_string())
-          .collect::<Vec<String>>()
-          .join(" ")
+      .map(|row| {
+        let codes: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+        codes.join(" ")
       })
-      .collect::<Vec<String>>()
-      .join("\n");
-    write!(f, "{}", result)
+      .collect();
+    write!(f, "{}", lines.join("\n"))
   }
 }
@@ -0,0 +1,282 @@
+use crate::full_state::{calc_full_state, Layout};
+use crate::net::protocol::{
+  read_msg, write_msg, ClientMsg, NetFrame, ServerMsg, PROTOCOL_VERSION,
+};
+use crate::{Direction, Food, PlayerInfo, Point, SnakeCtrlFullState};
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to a `Server`. The game is mirrored locally from the
+/// frames and deltas the server sends, `poll` applies them.
+pub struct Client {
+  stream: TcpStream,
+  incoming: Receiver<ServerMsg>,
+  reader: Option<JoinHandle<()>>,
+  player: usize,
+  token: u64,
+  players: usize,
+  dimension_x: u16,
+  dimension_y: u16,
+  walls: Vec<Point>,
+  portals: Vec<(Point, Point)>,
+  seq: u64,
+  frame: Option<NetFrame>,
+  waiting_full: bool,
+  joined: usize,
+  winner: Option<usize>,
+  is_over: bool,
+  rtt: Option<Duration>,
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn rejected(reason: String) -> io::Error {
+  io::Error::new(io::ErrorKind::ConnectionRefused, reason)
+}
+
+impl Client {
+  /// Joins the game. Pass the token of an earlier connection to take the
+  /// same player back after a disconnect.
+  pub fn connect<A: ToSocketAddrs>(
+    addr: A,
+    token: Option<u64>,
+  ) -> io::Result<Self> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    write_msg(
+      &mut stream,
+      &ClientMsg::Hello {
+        version: PROTOCOL_VERSION,
+        token,
+      },
+    )?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let welcome = read_msg(&mut reader)?;
+    stream.set_read_timeout(None)?;
+    let (player, token, players, dimension_x, dimension_y, walls, portals) =
+      match welcome {
+        Some(ServerMsg::Welcome {
+          version,
+          player,
+          token,
+          players,
+          dimension_x,
+          dimension_y,
+          walls,
+          portals,
+        }) if version == PROTOCOL_VERSION => (
+          player,
+          token,
+          players,
+          dimension_x,
+          dimension_y,
+          walls,
+          portals,
+        ),
+        Some(ServerMsg::Rejected { reason }) => return Err(rejected(reason)),
+        _ => return Err(rejected("unexpected answer".to_owned())),
+      };
+
+    let (tx, incoming) = mpsc::channel();
+    let reader_thread = thread::spawn(move || {
+      while let Ok(Some(msg)) = read_msg(&mut reader) {
+        if tx.send(msg).is_err() {
+          break;
+        }
+      }
+    });
+
+    Ok(Client {
+      stream,
+      incoming,
+      reader: Some(reader_thread),
+      player,
+      token,
+      players,
+      dimension_x,
+      dimension_y,
+      walls,
+      portals,
+      seq: 0,
+      frame: None,
+      waiting_full: true,
+      joined: 0,
+      winner: None,
+      is_over: false,
+      rtt: None,
+    })
+  }
+
+  pub fn player(&self) -> usize {
+    self.player
+  }
+
+  /// Keep it to reconnect as the same player.
+  pub fn token(&self) -> u64 {
+    self.token
+  }
+
+  pub fn dimensions(&self) -> (u16, u16) {
+    (self.dimension_x, self.dimension_y)
+  }
+
+  pub fn walls(&self) -> &[Point] {
+    &self.walls
+  }
+
+  pub fn portals(&self) -> &[(Point, Point)] {
+    &self.portals
+  }
+
+  pub fn direction_to(&mut self, direction: Direction) -> io::Result<()> {
+    self.seq += 1;
+    let seq = self.seq;
+    write_msg(&mut self.stream, &ClientMsg::Input { seq, direction })
+  }
+
+  /// Measures the round trip time, see `rtt`.
+  pub fn ping(&mut self) -> io::Result<()> {
+    let sent_at = now_millis();
+    write_msg(&mut self.stream, &ClientMsg::Ping { sent_at })
+  }
+
+  /// Applies everything received so far. Returns whether the game changed,
+  /// an `Err` means the connection is lost.
+  pub fn poll(&mut self) -> io::Result<bool> {
+    let mut changed = false;
+    loop {
+      let msg = match self.incoming.try_recv() {
+        Ok(msg) => msg,
+        Err(TryRecvError::Empty) => return Ok(changed),
+        Err(TryRecvError::Disconnected) => {
+          return Err(io::ErrorKind::ConnectionAborted.into())
+        }
+      };
+      match msg {
+        ServerMsg::Full(frame) => {
+          self.is_over = false;
+          self.winner = None;
+          self.frame = Some(frame);
+          self.waiting_full = false;
+          changed = true;
+        }
+        ServerMsg::Delta(delta) => {
+          let applied = match &mut self.frame {
+            Some(frame) if !self.waiting_full => frame.apply(&delta),
+            _ => false,
+          };
+          if applied {
+            changed = true;
+          } else if !self.waiting_full {
+            self.waiting_full = true;
+            write_msg(&mut self.stream, &ClientMsg::Resync)?;
+          }
+        }
+        ServerMsg::GameOver { winner } => {
+          self.is_over = true;
+          self.winner = winner;
+          changed = true;
+        }
+        ServerMsg::Waiting { joined, .. } => self.joined = joined,
+        ServerMsg::Pong { sent_at } => {
+          let millis = now_millis().saturating_sub(sent_at);
+          self.rtt = Some(Duration::from_millis(millis));
+        }
+        ServerMsg::Rejected { reason } => return Err(rejected(reason)),
+        ServerMsg::Welcome { .. } => {}
+      }
+    }
+  }
+
+  /// Whether the game runs, it waits for other players otherwise.
+  pub fn is_started(&self) -> bool {
+    self.frame.is_some()
+  }
+
+  /// Players joined while waiting for the game to start.
+  pub fn joined(&self) -> usize {
+    self.joined.max(1)
+  }
+
+  pub fn players_expected(&self) -> usize {
+    self.players
+  }
+
+  pub fn tick(&self) -> u64 {
+    self.frame.as_ref().map_or(0, |f| f.tick)
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.is_over
+  }
+
+  pub fn winner(&self) -> Option<usize> {
+    self.winner
+  }
+
+  pub fn rtt(&self) -> Option<Duration> {
+    self.rtt
+  }
+
+  pub fn players(&self) -> Vec<PlayerInfo> {
+    let snakes = self.frame.iter().flat_map(|f| f.snakes.iter());
+    snakes
+      .map(|s| PlayerInfo {
+        id: s.id,
+        score: s.score,
+        length: s.body.len(),
+        is_alive: s.is_alive,
+        direction: s.direction,
+      })
+      .collect()
+  }
+
+  pub fn food(&self) -> Vec<Food> {
+    self
+      .frame
+      .as_ref()
+      .map_or_else(Vec::new, |f| f.food.clone())
+  }
+
+  pub fn full_state(
+    &self,
+    id: usize,
+    reverse_y: bool,
+  ) -> Option<SnakeCtrlFullState> {
+    let frame = self.frame.as_ref()?;
+    let snake = frame.snakes.iter().find(|s| s.id == id)?;
+    Some(calc_full_state(
+      &snake.body,
+      &frame.food,
+      snake.direction,
+      &Layout {
+        dim_x: self.dimension_x,
+        dim_y: self.dimension_y,
+        portals: &self.portals,
+      },
+      reverse_y,
+    ))
+  }
+}
+
+/// Closes the connection, so the server sees the player leave at once,
+/// and waits for the reader thread.
+impl Drop for Client {
+  fn drop(&mut self) {
+    let _ = self.stream.shutdown(Shutdown::Both);
+    if let Some(reader) = self.reader.take() {
+      let _ = reader.join();
+    }
+  }
+}
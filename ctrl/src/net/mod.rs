@@ -0,0 +1,15 @@
+//! Networked games: a `Server` runs the authoritative game loop and
+//! `Client`s send their inputs and mirror the game it sends back.
+//!
+//! Messages are lines of JSON, see `protocol`. The server sends a full
+//! frame when a client joins and deltas after every tick. A client that
+//! misses a delta asks for a full frame again, one that reads too slowly
+//! gets deltas skipped until it catches up. A dropped player keeps its seat
+//! and gets it back by connecting with its token.
+
+mod client;
+pub mod protocol;
+mod server;
+
+pub use client::Client;
+pub use server::{Server, ServerHandle, ServerOptions};
@@ -0,0 +1,199 @@
+use crate::{Direction, Food, Point};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+
+/// Bumped on every incompatible change of the messages below. Peers with a
+/// different version are rejected on `Hello`.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// Messages sent by a client. Every message is one line of JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMsg {
+  /// First message of a connection. `token` is the one from an earlier
+  /// `Welcome` when the player reconnects.
+  Hello {
+    version: u32,
+    token: Option<u64>,
+  },
+  /// `seq` grows with every input, older inputs arriving late are dropped.
+  Input {
+    seq: u64,
+    direction: Direction,
+  },
+  /// Asks for a full frame, e.g. after a missed delta.
+  Resync,
+  Ping {
+    sent_at: u64,
+  },
+}
+
+/// Messages sent by the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMsg {
+  Welcome {
+    version: u32,
+    player: usize,
+    token: u64,
+    players: usize,
+    dimension_x: u16,
+    dimension_y: u16,
+    walls: Vec<Point>,
+    portals: Vec<(Point, Point)>,
+  },
+  Rejected {
+    reason: String,
+  },
+  /// The game starts when every player has joined.
+  Waiting {
+    joined: usize,
+    players: usize,
+  },
+  Full(NetFrame),
+  Delta(NetDelta),
+  GameOver {
+    winner: Option<usize>,
+  },
+  Pong {
+    sent_at: u64,
+  },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetSnake {
+  pub id: usize,
+  pub body: Vec<Point>,
+  pub direction: Direction,
+  pub score: u64,
+  pub is_alive: bool,
+}
+
+/// The whole game as seen after `tick`. `round` changes on every restart.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetFrame {
+  pub round: u64,
+  pub tick: u64,
+  pub snakes: Vec<NetSnake>,
+  pub food: Vec<Food>,
+}
+
+/// Change of one snake: `heads` are put in front of the old body, which is
+/// then cut to `length`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetSnakeDelta {
+  pub id: usize,
+  pub heads: Vec<Point>,
+  pub length: usize,
+  pub direction: Direction,
+  pub score: u64,
+  pub is_alive: bool,
+}
+
+/// Changes made by one tick. It applies only to the frame of `tick - 1`
+/// in the same round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetDelta {
+  pub round: u64,
+  pub tick: u64,
+  pub snakes: Vec<NetSnakeDelta>,
+  pub food: Vec<Food>,
+}
+
+impl NetSnakeDelta {
+  /// `None` when `new` is not `old` moved forward, a full frame is needed
+  /// then.
+  pub(crate) fn between(old: &NetSnake, new: &NetSnake) -> Option<Self> {
+    let k = new.body.iter().position(|p| *p == old.body[0])?;
+    let kept = &new.body[k..];
+    if kept.len() > old.body.len() || kept != &old.body[..kept.len()] {
+      return None;
+    }
+    Some(NetSnakeDelta {
+      id: new.id,
+      heads: new.body[..k].to_vec(),
+      length: new.body.len(),
+      direction: new.direction,
+      score: new.score,
+      is_alive: new.is_alive,
+    })
+  }
+
+  /// Returns false when the delta does not fit the snake.
+  pub(crate) fn apply(&self, snake: &mut NetSnake) -> bool {
+    if self.heads.len() + snake.body.len() < self.length {
+      return false;
+    }
+    let mut body = self.heads.clone();
+    body.extend_from_slice(&snake.body);
+    body.truncate(self.length);
+    snake.body = body;
+    snake.direction = self.direction;
+    snake.score = self.score;
+    snake.is_alive = self.is_alive;
+    true
+  }
+}
+
+impl NetFrame {
+  pub(crate) fn delta_to(&self, next: &NetFrame) -> Option<NetDelta> {
+    if self.round != next.round || self.snakes.len() != next.snakes.len() {
+      return None;
+    }
+    let snakes = self
+      .snakes
+      .iter()
+      .zip(&next.snakes)
+      .map(|(old, new)| NetSnakeDelta::between(old, new))
+      .collect::<Option<Vec<_>>>()?;
+    Some(NetDelta {
+      round: next.round,
+      tick: next.tick,
+      snakes,
+      food: next.food.clone(),
+    })
+  }
+
+  /// Returns false when the delta does not follow this frame, the frame is
+  /// left as it was then.
+  pub(crate) fn apply(&mut self, delta: &NetDelta) -> bool {
+    if delta.round != self.round
+      || delta.tick != self.tick + 1
+      || delta.snakes.len() != self.snakes.len()
+    {
+      return false;
+    }
+    let mut snakes = self.snakes.clone();
+    for (snake, d) in snakes.iter_mut().zip(&delta.snakes) {
+      if snake.id != d.id || !d.apply(snake) {
+        return false;
+      }
+    }
+    self.snakes = snakes;
+    self.tick = delta.tick;
+    self.food = delta.food.clone();
+    true
+  }
+}
+
+pub(crate) fn write_msg<W: Write, T: Serialize>(
+  w: &mut W,
+  msg: &T,
+) -> io::Result<()> {
+  let mut line = serde_json::to_vec(msg)?;
+  line.push(b'\n');
+  w.write_all(&line)?;
+  w.flush()
+}
+
+/// `Ok(None)` on a closed connection.
+pub(crate) fn read_msg<R: BufRead, T: DeserializeOwned>(
+  r: &mut R,
+) -> io::Result<Option<T>> {
+  let mut line = String::new();
+  if r.read_line(&mut line)? == 0 {
+    return Ok(None);
+  }
+  Ok(Some(serde_json::from_str(&line)?))
+}
@@ -0,0 +1,449 @@
+use crate::net::protocol::{
+  read_msg, write_msg, ClientMsg, NetFrame, ServerMsg, PROTOCOL_VERSION,
+};
+use crate::options::InnerCfg;
+use crate::{SnakeCtrl, SnakeCtrlOptions, SnakeCtrlResult, TickEvent};
+use std::collections::HashMap;
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+static DEF_TICK_MILLIS: u64 = 150;
+static DEF_SEND_BUFFER: usize = 8;
+static DEF_RESTART_DELAY: u64 = 20;
+
+#[derive(Clone)]
+pub struct ServerOptions {
+  game: SnakeCtrlOptions,
+  tick_millis: u64,
+  send_buffer: usize,
+  restart_delay: u64,
+}
+
+impl ServerOptions {
+  /// The game starts once `players` set in `game` have joined.
+  pub fn new(game: &SnakeCtrlOptions) -> Self {
+    ServerOptions {
+      game: game.clone(),
+      tick_millis: DEF_TICK_MILLIS,
+      send_buffer: DEF_SEND_BUFFER,
+      restart_delay: DEF_RESTART_DELAY,
+    }
+  }
+  pub fn tick_millis(mut self, val: u64) -> Self {
+    self.tick_millis = val.max(1);
+    self
+  }
+  /// Messages queued for a client before it counts as lagging. A lagging
+  /// client skips deltas and gets a full frame once it catches up.
+  pub fn send_buffer(mut self, val: usize) -> Self {
+    self.send_buffer = val.max(1);
+    self
+  }
+  /// Ticks between the end of a game and the next one.
+  pub fn restart_delay(mut self, val: u64) -> Self {
+    self.restart_delay = val;
+    self
+  }
+}
+
+enum Event {
+  Joined(usize, TcpStream, Option<u64>),
+  Msg(usize, ClientMsg),
+  Left(usize),
+}
+
+struct Conn {
+  player: usize,
+  out: SyncSender<ServerMsg>,
+  needs_full: bool,
+}
+
+/// A player seat. It is kept when the connection drops, the player can
+/// take it back with the token.
+struct Slot {
+  token: u64,
+  conn: Option<usize>,
+  last_seq: u64,
+}
+
+/// Runs the authoritative game and talks to clients over TCP.
+pub struct Server {
+  listener: TcpListener,
+  opts: ServerOptions,
+  stop: Arc<AtomicBool>,
+}
+
+/// A server running on its own thread, stopped on drop.
+pub struct ServerHandle {
+  addr: SocketAddr,
+  stop: Arc<AtomicBool>,
+  thread: Option<thread::JoinHandle<io::Result<()>>>,
+}
+
+impl ServerHandle {
+  pub fn local_addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  pub fn stop(mut self) -> io::Result<()> {
+    self.stop.store(true, Ordering::Relaxed);
+    match self.thread.take().map(|t| t.join()) {
+      Some(Ok(res)) => res,
+      Some(Err(_)) => Err(io::Error::other("the server panicked")),
+      None => Ok(()),
+    }
+  }
+}
+
+impl Drop for ServerHandle {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+  }
+}
+
+impl Server {
+  pub fn bind<A: ToSocketAddrs>(
+    addr: A,
+    opts: &ServerOptions,
+  ) -> io::Result<Self> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(Server {
+      listener,
+      opts: opts.clone(),
+      stop: Arc::new(AtomicBool::new(false)),
+    })
+  }
+
+  pub fn local_addr(&self) -> io::Result<SocketAddr> {
+    self.listener.local_addr()
+  }
+
+  pub fn spawn(self) -> io::Result<ServerHandle> {
+    let addr = self.local_addr()?;
+    let stop = self.stop.clone();
+    let thread = thread::spawn(move || self.run());
+    Ok(ServerHandle {
+      addr,
+      stop,
+      thread: Some(thread),
+    })
+  }
+
+  /// Blocks until the server is stopped through its `ServerHandle`, or
+  /// fails when the game cannot start once every player joined.
+  pub fn run(self) -> io::Result<()> {
+    let (events_tx, events) = mpsc::channel();
+    let mut state = ServerState::new(&self.opts);
+    let tick = Duration::from_millis(self.opts.tick_millis);
+    let mut next_tick = Instant::now() + tick;
+    let mut next_conn = 0;
+
+    while !self.stop.load(Ordering::Relaxed) {
+      loop {
+        match self.listener.accept() {
+          Ok((stream, _)) => {
+            spawn_reader(next_conn, stream, events_tx.clone());
+            next_conn += 1;
+          }
+          Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+          Err(e) => return Err(e),
+        }
+      }
+
+      state.handle_events(&events).map_err(io::Error::other)?;
+
+      let now = Instant::now();
+      if now >= next_tick {
+        state.tick();
+        next_tick += tick;
+        if next_tick < now {
+          next_tick = now + tick;
+        }
+      } else {
+        thread::sleep((next_tick - now).min(Duration::from_millis(5)));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Reads the `Hello` and then every message of one connection.
+fn spawn_reader(conn: usize, stream: TcpStream, events: Sender<Event>) {
+  thread::spawn(move || {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_nodelay(true);
+    let mut reader = match stream.try_clone() {
+      Ok(s) => BufReader::new(s),
+      Err(_) => return,
+    };
+    match read_msg(&mut reader) {
+      Ok(Some(ClientMsg::Hello { version, token })) => {
+        if version != PROTOCOL_VERSION {
+          let reason =
+            format!("protocol version {} is expected", PROTOCOL_VERSION);
+          let mut stream = stream;
+          let _ = write_msg(&mut stream, &ServerMsg::Rejected { reason });
+          return;
+        }
+        if events.send(Event::Joined(conn, stream, token)).is_err() {
+          return;
+        }
+      }
+      _ => return,
+    }
+    while let Ok(Some(msg)) = read_msg(&mut reader) {
+      if events.send(Event::Msg(conn, msg)).is_err() {
+        return;
+      }
+    }
+    let _ = events.send(Event::Left(conn));
+  });
+}
+
+fn spawn_writer(mut stream: TcpStream, buffer: usize) -> SyncSender<ServerMsg> {
+  let (tx, rx) = mpsc::sync_channel::<ServerMsg>(buffer);
+  thread::spawn(move || {
+    for msg in rx {
+      if write_msg(&mut stream, &msg).is_err() {
+        break;
+      }
+    }
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+  });
+  tx
+}
+
+struct ServerState {
+  cfg: InnerCfg,
+  send_buffer: usize,
+  restart_delay: u64,
+  ctrl: Option<SnakeCtrl>,
+  round: u64,
+  frame: Option<NetFrame>,
+  over_for: Option<u64>,
+  slots: Vec<Slot>,
+  conns: HashMap<usize, Conn>,
+}
+
+impl ServerState {
+  fn new(opts: &ServerOptions) -> Self {
+    ServerState {
+      cfg: InnerCfg::from_options(&opts.game),
+      send_buffer: opts.send_buffer,
+      restart_delay: opts.restart_delay,
+      ctrl: None,
+      round: 0,
+      frame: None,
+      over_for: None,
+      slots: vec![],
+      conns: HashMap::new(),
+    }
+  }
+
+  fn players(&self) -> usize {
+    usize::from(self.cfg.players)
+  }
+
+  fn handle_events(&mut self, events: &Receiver<Event>) -> SnakeCtrlResult<()> {
+    while let Ok(event) = events.try_recv() {
+      match event {
+        Event::Joined(conn, stream, token) => self.join(conn, stream, token)?,
+        Event::Msg(conn, msg) => self.handle_msg(conn, msg),
+        Event::Left(conn) => {
+          if let Some(c) = self.conns.remove(&conn) {
+            self.slots[c.player].conn = None;
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn join(
+    &mut self,
+    conn: usize,
+    stream: TcpStream,
+    token: Option<u64>,
+  ) -> SnakeCtrlResult<()> {
+    let slot = token.and_then(|t| self.slots.iter().position(|s| s.token == t));
+    let player = match slot {
+      Some(ind) => ind,
+      None if self.slots.len() < self.players() => {
+        self.slots.push(Slot {
+          token: rand::random(),
+          conn: None,
+          last_seq: 0,
+        });
+        self.slots.len() - 1
+      }
+      None => {
+        let reason = "the server is full".to_owned();
+        let mut stream = stream;
+        let _ = write_msg(&mut stream, &ServerMsg::Rejected { reason });
+        return Ok(());
+      }
+    };
+
+    if let Some(old) = self.slots[player].conn.replace(conn) {
+      self.conns.remove(&old);
+    }
+    // A new connection numbers its inputs from the start again.
+    self.slots[player].last_seq = 0;
+    let out = spawn_writer(stream, self.send_buffer);
+    let _ = out.try_send(ServerMsg::Welcome {
+      version: PROTOCOL_VERSION,
+      player,
+      token: self.slots[player].token,
+      players: self.players(),
+      dimension_x: self.cfg.dimension_x,
+      dimension_y: self.cfg.dimension_y,
+      walls: self.cfg.walls.clone(),
+      portals: self.cfg.portals.clone(),
+    });
+    self.conns.insert(
+      conn,
+      Conn {
+        player,
+        out,
+        needs_full: true,
+      },
+    );
+
+    if self.ctrl.is_none() && self.slots.len() == self.players() {
+      self.start()?;
+    } else if self.ctrl.is_none() {
+      let waiting = ServerMsg::Waiting {
+        joined: self.slots.len(),
+        players: self.players(),
+      };
+      self.broadcast(waiting);
+    }
+    Ok(())
+  }
+
+  /// Starts the game, the clients are turned away when it cannot start.
+  fn start(&mut self) -> SnakeCtrlResult<()> {
+    match SnakeCtrl::from_cfg(self.cfg.clone()) {
+      Ok(ctrl) => {
+        self.frame = Some(ctrl.net_frame(self.round));
+        self.ctrl = Some(ctrl);
+        self.conns.values_mut().for_each(|c| c.needs_full = true);
+        Ok(())
+      }
+      Err(e) => {
+        let reason = format!("unable to start the game: {}", e);
+        self.broadcast(ServerMsg::Rejected { reason });
+        Err(e)
+      }
+    }
+  }
+
+  fn handle_msg(&mut self, conn: usize, msg: ClientMsg) {
+    let c = match self.conns.get_mut(&conn) {
+      Some(c) => c,
+      None => return,
+    };
+    match msg {
+      ClientMsg::Input { seq, direction } => {
+        let slot = &mut self.slots[c.player];
+        if seq <= slot.last_seq {
+          return;
+        }
+        slot.last_seq = seq;
+        if let Some(ctrl) = &mut self.ctrl {
+          let _ = ctrl.direction_to_player(c.player, direction);
+        }
+      }
+      ClientMsg::Resync => c.needs_full = true,
+      ClientMsg::Ping { sent_at } => {
+        let _ = c.out.try_send(ServerMsg::Pong { sent_at });
+      }
+      ClientMsg::Hello { .. } => {}
+    }
+  }
+
+  fn tick(&mut self) {
+    let ctrl = match &mut self.ctrl {
+      Some(c) => c,
+      None => return,
+    };
+
+    if let Some(ticks) = &mut self.over_for {
+      *ticks += 1;
+      if *ticks < self.restart_delay {
+        return;
+      }
+      self.over_for = None;
+      self.round += 1;
+      if ctrl.restart().is_err() {
+        return;
+      }
+      self.frame = Some(ctrl.net_frame(self.round));
+      self.conns.values_mut().for_each(|c| c.needs_full = true);
+    } else {
+      let outcome = match ctrl.next_tick() {
+        Ok(o) => o,
+        Err(_) => return,
+      };
+      let frame = ctrl.net_frame(self.round);
+      let delta = self.frame.as_ref().and_then(|f| f.delta_to(&frame));
+      if delta.is_none() {
+        self.conns.values_mut().for_each(|c| c.needs_full = true);
+      }
+      self.frame = Some(frame);
+      if let Some(delta) = delta {
+        self.broadcast(ServerMsg::Delta(delta));
+      }
+      if outcome.is_over() {
+        self.over_for = Some(0);
+        let winner = outcome.events.iter().find_map(|e| match e {
+          TickEvent::Won(id) => Some(*id),
+          _ => None,
+        });
+        self.broadcast(ServerMsg::GameOver { winner });
+      }
+    }
+    self.send_full_frames();
+  }
+
+  /// Sends a full frame to the clients that joined or lag behind.
+  fn send_full_frames(&mut self) {
+    let frame = match &self.frame {
+      Some(f) => f,
+      None => return,
+    };
+    for c in self.conns.values_mut().filter(|c| c.needs_full) {
+      if c.out.try_send(ServerMsg::Full(frame.clone())).is_ok() {
+        c.needs_full = false;
+      }
+    }
+  }
+
+  /// Sends to every client, deltas are skipped for the ones waiting for a
+  /// full frame. A client whose queue is full is marked as lagging.
+  fn broadcast(&mut self, msg: ServerMsg) {
+    let is_delta = matches!(msg, ServerMsg::Delta(_));
+    let mut gone = vec![];
+    for (id, c) in self.conns.iter_mut() {
+      if is_delta && c.needs_full {
+        continue;
+      }
+      match c.out.try_send(msg.clone()) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => c.needs_full = true,
+        Err(TrySendError::Disconnected(_)) => gone.push(*id),
+      }
+    }
+    for id in gone {
+      if let Some(c) = self.conns.remove(&id) {
+        self.slots[c.player].conn = None;
+      }
+    }
+  }
+}
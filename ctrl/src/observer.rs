@@ -0,0 +1,60 @@
+use crate::tick::{DeathCause, TickEvent, TickOutcome};
+use crate::{FoodKind, Point};
+use std::sync::{Mutex, PoisonError};
+
+/// Gets told what happens in a game registered with
+/// `SnakeCtrl::add_observer`. Every method does nothing by default, so an
+/// observer only implements the ones it needs. Observers are `Send`, so
+/// the game they watch can move to another thread.
+pub trait Observer: Send {
+  fn on_food_eaten(&mut self, _snake: usize, _at: Point, _kind: FoodKind) {}
+
+  fn on_food_spawned(&mut self, _at: Point) {}
+
+  fn on_death(&mut self, _snake: usize, _cause: DeathCause, _at: Point) {}
+
+  /// The head went through an edge or a portal.
+  fn on_wrap(&mut self, _snake: usize, _from: Point, _to: Point) {}
+
+  /// Followed by `on_food_spawned` for the food the new game starts with.
+  fn on_restart(&mut self, _seed: u64) {}
+
+  /// Called after the events of the tick, `tick` counts from 1.
+  fn on_tick(&mut self, _tick: u64, _outcome: &TickOutcome) {}
+}
+
+/// The observers of a game. The `Mutex` is never locked, it only keeps the
+/// game `Sync` with observers that are just `Send`.
+#[derive(Default)]
+pub(crate) struct Observers(Mutex<Vec<Box<dyn Observer>>>);
+
+impl Observers {
+  pub(crate) fn get_mut(&mut self) -> &mut Vec<Box<dyn Observer>> {
+    self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+pub(crate) fn notify(
+  observers: &mut [Box<dyn Observer>],
+  tick: u64,
+  outcome: &TickOutcome,
+) {
+  for observer in observers {
+    for event in &outcome.events {
+      match *event {
+        TickEvent::Ate { snake, at, kind } => {
+          observer.on_food_eaten(snake, at, kind)
+        }
+        TickEvent::FoodSpawned(at) => observer.on_food_spawned(at),
+        TickEvent::Died { snake, cause, at } => {
+          observer.on_death(snake, cause, at)
+        }
+        TickEvent::Teleported { snake, from, to } => {
+          observer.on_wrap(snake, from, to)
+        }
+        _ => {}
+      }
+    }
+    observer.on_tick(tick, outcome);
+  }
+}
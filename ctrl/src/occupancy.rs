@@ -0,0 +1,58 @@
+use crate::Point;
+
+/// The cells of the board as a grid, indexed by `y * dim_x + x`: which
+/// alive snake covers each of them and where the food is. `Board` keeps it
+/// up to date on every move, so lookups don't scan the bodies.
+#[derive(Clone)]
+pub(crate) struct Occupancy {
+  dim_x: usize,
+  owners: Vec<Option<u16>>,
+  food: Vec<bool>,
+}
+
+impl Occupancy {
+  pub(crate) fn new(dim_x: u16, dim_y: u16) -> Self {
+    let cells = usize::from(dim_x) * usize::from(dim_y);
+    Occupancy {
+      dim_x: usize::from(dim_x),
+      owners: vec![None; cells],
+      food: vec![false; cells],
+    }
+  }
+
+  fn index(&self, p: Point) -> usize {
+    usize::from(p.1) * self.dim_x + usize::from(p.0)
+  }
+
+  /// Index of the snake on the cell.
+  pub(crate) fn owner(&self, p: Point) -> Option<usize> {
+    self.owners[self.index(p)].map(usize::from)
+  }
+
+  pub(crate) fn set_owner(&mut self, p: Point, snake: usize) {
+    let ind = self.index(p);
+    self.owners[ind] = Some(snake as u16);
+  }
+
+  /// Frees the cell unless another snake took it already.
+  pub(crate) fn release(&mut self, p: Point, snake: usize) {
+    let ind = self.index(p);
+    if self.owners[ind] == Some(snake as u16) {
+      self.owners[ind] = None;
+    }
+  }
+
+  pub(crate) fn has_food(&self, p: Point) -> bool {
+    self.food[self.index(p)]
+  }
+
+  pub(crate) fn set_food(&mut self, p: Point, val: bool) {
+    let ind = self.index(p);
+    self.food[ind] = val;
+  }
+
+  /// Neither a snake nor food is on the cell.
+  pub(crate) fn is_free(&self, ind: usize) -> bool {
+    self.owners[ind].is_none() && !self.food[ind]
+  }
+}
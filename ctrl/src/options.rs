@@ -1,11 +1,143 @@
+use crate::food::FoodSpawn;
+use crate::{Direction, Point};
+use std::str::FromStr;
+use thiserror::Error;
+
 static DEF_DIMENSION_X: u16 = 7;
 static DEF_DIMENSION_Y: u16 = 7;
 static DEF_INITIAL_SNAKE_SIZE: u16 = 3;
 static DEF_WALKING_THROUGH_THE_WALLS: bool = true;
 static DEF_FAIL_ON_REVERT: bool = false;
 static DEF_AUTO_GEN_FOOD: bool = true;
+static DEF_PLAYERS: u8 = 1;
+static DEF_GROWTH_PER_FOOD: u16 = 1;
+static DEF_INPUT_QUEUE_DEPTH: u8 = 1;
+
+/// What happens to a snake that dies while others are still playing.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(eq, eq_int))]
+pub enum DeathRule {
+  /// The whole game is over.
+  #[default]
+  EndGame,
+  /// The snake leaves the board, the rest keep playing.
+  Remove,
+  /// The snake leaves the board and its body turns into food.
+  TurnIntoFood,
+}
+
+/// Where a snake starts: its head, the way it faces and, optionally, the
+/// rest of its body.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnakeStart {
+  pub head: Point,
+  pub direction: Direction,
+  /// Cells from the neck to the tail, each next to the previous one. When
+  /// empty the body goes straight back from the head and has
+  /// `initial_snake_size` cells in total.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub body: Vec<Point>,
+}
+
+impl SnakeStart {
+  /// A straight snake.
+  pub fn new(head: Point, direction: Direction) -> Self {
+    SnakeStart {
+      head,
+      direction,
+      body: vec![],
+    }
+  }
+
+  pub fn body(mut self, val: Vec<Point>) -> Self {
+    self.body = val;
+    self
+  }
+}
 
-#[derive(Default)]
+/// Edges a snake goes through to come out on the opposite side, leaving
+/// through any other edge kills it.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WrapEdges {
+  pub left: bool,
+  pub right: bool,
+  pub top: bool,
+  pub bottom: bool,
+}
+
+impl WrapEdges {
+  pub fn all() -> Self {
+    WrapEdges {
+      left: true,
+      right: true,
+      top: true,
+      bottom: true,
+    }
+  }
+
+  pub fn none() -> Self {
+    WrapEdges::default()
+  }
+
+  /// Left and right edges, the board is a horizontal tube.
+  pub fn horizontal() -> Self {
+    WrapEdges {
+      left: true,
+      right: true,
+      ..WrapEdges::default()
+    }
+  }
+
+  /// Top and bottom edges, the board is a vertical tube.
+  pub fn vertical() -> Self {
+    WrapEdges {
+      top: true,
+      bottom: true,
+      ..WrapEdges::default()
+    }
+  }
+}
+
+#[derive(Error, Clone, Debug, Eq, PartialEq)]
+#[error("expected `true`, `false`, `horizontal`, `vertical` or edge names")]
+pub struct ParseWrapEdgesError;
+
+/// Parses `true`, `false`, `horizontal`, `vertical` or a list of `left`,
+/// `right`, `top` and `bottom` separated by spaces or commas.
+impl FromStr for WrapEdges {
+  type Err = ParseWrapEdgesError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim() {
+      "true" => return Ok(WrapEdges::all()),
+      "false" => return Ok(WrapEdges::none()),
+      "horizontal" => return Ok(WrapEdges::horizontal()),
+      "vertical" => return Ok(WrapEdges::vertical()),
+      _ => {}
+    }
+    let mut edges = WrapEdges::none();
+    let names = s.split(|c: char| c == ',' || c.is_whitespace());
+    for name in names.filter(|n| !n.is_empty()) {
+      match name {
+        "left" => edges.left = true,
+        "right" => edges.right = true,
+        "top" => edges.top = true,
+        "bottom" => edges.bottom = true,
+        _ => return Err(ParseWrapEdgesError),
+      }
+    }
+    if edges == WrapEdges::none() {
+      return Err(ParseWrapEdgesError);
+    }
+    Ok(edges)
+  }
+}
+
+#[derive(Clone, Default)]
 pub struct Options {
   dimension_x: Option<u16>,
   dimension_y: Option<u16>,
@@ -13,6 +145,20 @@ pub struct Options {
   walking_through_the_walls: Option<bool>,
   fail_on_revert: Option<bool>,
   auto_gen_food: Option<bool>,
+  seed: Option<u64>,
+  players: Option<u8>,
+  death_rule: Option<DeathRule>,
+  walls: Option<Vec<Point>>,
+  starts: Option<Vec<SnakeStart>>,
+  food: Option<Vec<Point>>,
+  food_spawns: Option<Vec<FoodSpawn>>,
+  growth_per_food: Option<u16>,
+  wrap_edges: Option<WrapEdges>,
+  portals: Option<Vec<(Point, Point)>>,
+  start_head: Option<Point>,
+  start_direction: Option<Direction>,
+  start_body: Option<Vec<Point>>,
+  input_queue_depth: Option<u8>,
 }
 
 impl Options {
@@ -40,8 +186,88 @@ impl Options {
     self.auto_gen_food = Some(val);
     self
   }
+  /// Seed for food placement. Two games with the same seed and the same
+  /// inputs are identical. A random seed is picked when it is not set.
+  pub fn seed(mut self, val: u64) -> Self {
+    self.seed = Some(val);
+    self
+  }
+  /// Number of snakes on the board, one by default.
+  pub fn players(mut self, val: u8) -> Self {
+    self.players = Some(val);
+    self
+  }
+  pub fn death_rule(mut self, val: DeathRule) -> Self {
+    self.death_rule = Some(val);
+    self
+  }
+  /// Obstacle cells inside the board. Hitting one kills the snake.
+  pub fn walls(mut self, val: Vec<Point>) -> Self {
+    self.walls = Some(val);
+    self
+  }
+  /// Start of every player, in player order. Players without one are
+  /// placed automatically.
+  pub fn starts(mut self, val: Vec<SnakeStart>) -> Self {
+    self.starts = Some(val);
+    self
+  }
+  /// Head of the first snake, the centre of the board by default. The
+  /// `start_*` options are ignored when `starts` has the first snake.
+  pub fn start_head(mut self, val: Point) -> Self {
+    self.start_head = Some(val);
+    self
+  }
+  /// Way the first snake faces, right by default.
+  pub fn start_direction(mut self, val: Direction) -> Self {
+    self.start_direction = Some(val);
+    self
+  }
+  /// Cells of the first snake behind its head, see `SnakeStart::body`.
+  pub fn start_body(mut self, val: Vec<Point>) -> Self {
+    self.start_body = Some(val);
+    self
+  }
+  /// Food put on the board at the start instead of a random one. Fixed
+  /// food is always an apple that never expires, `food_spawns` only picks
+  /// the food that appears later.
+  pub fn food(mut self, val: Vec<Point>) -> Self {
+    self.food = Some(val);
+    self
+  }
+  /// Kinds of food that appear during the game, picked by weight. Only
+  /// apples that never expire appear by default.
+  pub fn food_spawns(mut self, val: Vec<FoodSpawn>) -> Self {
+    self.food_spawns = Some(val);
+    self
+  }
+  /// Cells a snake gets for one food, one by default. The snake grows one
+  /// cell per tick until it gets all of them.
+  pub fn growth_per_food(mut self, val: u16) -> Self {
+    self.growth_per_food = Some(val);
+    self
+  }
+  /// Turns a snake keeps for the next ticks, one is taken per tick. With
+  /// the default of one a new turn replaces the waiting one.
+  pub fn input_queue_depth(mut self, val: u8) -> Self {
+    self.input_queue_depth = Some(val);
+    self
+  }
+  /// Wrapping edge by edge, takes over `walking_through_the_walls`.
+  pub fn wrap_edges(mut self, val: WrapEdges) -> Self {
+    self.wrap_edges = Some(val);
+    self
+  }
+  /// Pairs of linked cells. A head entering one of them comes out on the
+  /// other one. No food appears on them.
+  pub fn portals(mut self, val: Vec<(Point, Point)>) -> Self {
+    self.portals = Some(val);
+    self
+  }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct InnerCfg {
   pub(crate) dimension_x: u16,
   pub(crate) dimension_y: u16,
@@ -49,29 +275,132 @@ pub(crate) struct InnerCfg {
   pub(crate) walking_through_the_walls: bool,
   pub(crate) fail_on_revert: bool,
   pub(crate) auto_gen_food: bool,
+  pub(crate) seed: u64,
+  pub(crate) players: u8,
+  pub(crate) death_rule: DeathRule,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) walls: Vec<Point>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) starts: Vec<SnakeStart>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) food: Vec<Point>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) food_spawns: Vec<FoodSpawn>,
+  #[cfg_attr(feature = "serde", serde(default = "def_growth_per_food"))]
+  pub(crate) growth_per_food: u16,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) wrap_edges: Option<WrapEdges>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub(crate) portals: Vec<(Point, Point)>,
+  #[cfg_attr(feature = "serde", serde(default = "def_input_queue_depth"))]
+  pub(crate) input_queue_depth: u8,
+}
+
+#[cfg(feature = "serde")]
+fn def_growth_per_food() -> u16 {
+  DEF_GROWTH_PER_FOOD
+}
+
+#[cfg(feature = "serde")]
+fn def_input_queue_depth() -> u8 {
+  DEF_INPUT_QUEUE_DEPTH
 }
 
 impl InnerCfg {
   pub(crate) fn from_options(opts: &Options) -> Self {
-    let snake_size = if let Some(s) = opts.initial_snake_size {
-      if s < 3 {
-        3
-      } else {
-        s
-      }
-    } else {
-      DEF_INITIAL_SNAKE_SIZE
-    };
-
-    InnerCfg {
+    let mut cfg = InnerCfg {
       dimension_x: opts.dimension_x.unwrap_or(DEF_DIMENSION_X),
       dimension_y: opts.dimension_y.unwrap_or(DEF_DIMENSION_Y),
-      initial_snake_size: snake_size,
+      initial_snake_size: opts
+        .initial_snake_size
+        .unwrap_or(DEF_INITIAL_SNAKE_SIZE),
       walking_through_the_walls: opts
         .walking_through_the_walls
         .unwrap_or(DEF_WALKING_THROUGH_THE_WALLS),
       fail_on_revert: opts.fail_on_revert.unwrap_or(DEF_FAIL_ON_REVERT),
       auto_gen_food: opts.auto_gen_food.unwrap_or(DEF_AUTO_GEN_FOOD),
+      seed: opts.seed.unwrap_or_else(rand::random),
+      players: opts.players.unwrap_or(DEF_PLAYERS),
+      death_rule: opts.death_rule.unwrap_or_default(),
+      walls: opts.walls.clone().unwrap_or_default(),
+      starts: opts.starts.clone().unwrap_or_default(),
+      food: opts.food.clone().unwrap_or_default(),
+      food_spawns: opts.food_spawns.clone().unwrap_or_default(),
+      growth_per_food: opts.growth_per_food.unwrap_or(DEF_GROWTH_PER_FOOD),
+      wrap_edges: opts.wrap_edges,
+      portals: opts.portals.clone().unwrap_or_default(),
+      input_queue_depth: opts
+        .input_queue_depth
+        .unwrap_or(DEF_INPUT_QUEUE_DEPTH),
+    }
+    .with_limits();
+
+    let has_start = opts.start_head.is_some()
+      || opts.start_direction.is_some()
+      || opts.start_body.is_some();
+    if cfg.starts.is_empty() && has_start {
+      let auto = cfg.auto_start(0);
+      cfg.starts.push(SnakeStart {
+        head: opts.start_head.unwrap_or(auto.head),
+        direction: opts.start_direction.unwrap_or(auto.direction),
+        body: opts.start_body.clone().unwrap_or_default(),
+      });
+    }
+    cfg
+  }
+
+  /// Start of a player missing from `starts`. Players are put on evenly
+  /// spaced rows: even ones face right and odd ones face left, a single
+  /// snake ends up in the centre.
+  pub(crate) fn auto_start(&self, id: u16) -> SnakeStart {
+    let players = u16::from(self.players);
+    let center_x = self.dimension_x / 2;
+    let y = (id + 1) * self.dimension_y / (players + 1);
+    if id % 2 == 1 {
+      SnakeStart::new(
+        Point(self.dimension_x - 1 - center_x, y),
+        Direction::Left,
+      )
+    } else {
+      SnakeStart::new(Point(center_x, y), Direction::Right)
+    }
+  }
+
+  /// Start of the given player, set or automatic.
+  pub(crate) fn start_of(&self, id: u16) -> SnakeStart {
+    match self.starts.get(usize::from(id)) {
+      Some(start) => start.clone(),
+      None => self.auto_start(id),
     }
   }
+
+  /// Raises the values a game cannot run with to the lowest ones it can.
+  /// Every config goes through it, built from `Options` or not.
+  pub(crate) fn with_limits(mut self) -> Self {
+    self.initial_snake_size = self.initial_snake_size.max(3);
+    self.players = self.players.max(1);
+    self.input_queue_depth = self.input_queue_depth.max(1);
+    self
+  }
+
+  pub(crate) fn wrap(&self) -> WrapEdges {
+    match self.wrap_edges {
+      Some(edges) => edges,
+      None if self.walking_through_the_walls => WrapEdges::all(),
+      None => WrapEdges::none(),
+    }
+  }
+
+  /// The other end of the portal at `p`.
+  pub(crate) fn portal_exit(&self, p: Point) -> Option<Point> {
+    self.portals.iter().find_map(|(a, b)| {
+      if *a == p {
+        Some(*b)
+      } else if *b == p {
+        Some(*a)
+      } else {
+        None
+      }
+    })
+  }
 }
@@ -0,0 +1,541 @@
+//! Python module over `SnakeCtrl`, built with the `pyo3` feature.
+//!
+//! Points are `(x, y)` tuples. `Matrix` and `Planes` export their cells
+//! through the buffer protocol, so `numpy.asarray` reads them without a
+//! copy.
+
+use crate::{
+  Cell, DeathCause, DeathRule, Direction, FoodKind, Glyphs, Point,
+  SnakeCornerVariant, SnakeCtrl, SnakeCtrlErr, SnakeCtrlMatrix,
+  SnakeCtrlOptions, SnakePartVariant, TickOutcome, WrapEdges,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyBufferError, PyException, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_int;
+use std::ptr;
+
+create_exception!(snake_ctrl, SnakeCtrlError, PyException);
+
+impl From<SnakeCtrlErr> for PyErr {
+  fn from(e: SnakeCtrlErr) -> Self {
+    SnakeCtrlError::new_err(e.to_string())
+  }
+}
+
+type PyPoint = (u16, u16);
+
+fn to_py(p: Point) -> PyPoint {
+  (p.0, p.1)
+}
+
+fn from_py(p: PyPoint) -> Point {
+  Point(p.0, p.1)
+}
+
+fn from_py_all(points: Vec<PyPoint>) -> Vec<Point> {
+  points.into_iter().map(from_py).collect()
+}
+
+#[pyclass(name = "SnakeCtrlOptions")]
+#[derive(Clone, Default)]
+struct PyOptions(SnakeCtrlOptions);
+
+#[pymethods]
+impl PyOptions {
+  /// Every argument is optional, the game picks its default for the ones
+  /// left out. `wrap_edges` takes the level header values, like
+  /// `"horizontal"` or `"left right"`.
+  #[new]
+  #[pyo3(signature = (
+    *,
+    dimension_x = None,
+    dimension_y = None,
+    initial_snake_size = None,
+    walking_through_the_walls = None,
+    fail_on_revert = None,
+    auto_gen_food = None,
+    seed = None,
+    players = None,
+    walls = None,
+    food = None,
+    growth_per_food = None,
+    input_queue_depth = None,
+    wrap_edges = None,
+    portals = None,
+    start_head = None,
+    start_direction = None,
+    start_body = None,
+    death_rule = None,
+  ))]
+  #[allow(clippy::too_many_arguments)]
+  fn new(
+    dimension_x: Option<u16>,
+    dimension_y: Option<u16>,
+    initial_snake_size: Option<u16>,
+    walking_through_the_walls: Option<bool>,
+    fail_on_revert: Option<bool>,
+    auto_gen_food: Option<bool>,
+    seed: Option<u64>,
+    players: Option<u8>,
+    walls: Option<Vec<PyPoint>>,
+    food: Option<Vec<PyPoint>>,
+    growth_per_food: Option<u16>,
+    input_queue_depth: Option<u8>,
+    wrap_edges: Option<&str>,
+    portals: Option<Vec<(PyPoint, PyPoint)>>,
+    start_head: Option<PyPoint>,
+    start_direction: Option<Direction>,
+    start_body: Option<Vec<PyPoint>>,
+    death_rule: Option<DeathRule>,
+  ) -> PyResult<Self> {
+    let mut opts = SnakeCtrlOptions::default();
+    if let Some(val) = dimension_x {
+      opts = opts.dimension_x(val);
+    }
+    if let Some(val) = dimension_y {
+      opts = opts.dimension_y(val);
+    }
+    if let Some(val) = initial_snake_size {
+      opts = opts.initial_snake_size(val);
+    }
+    if let Some(val) = walking_through_the_walls {
+      opts = opts.walking_through_the_walls(val);
+    }
+    if let Some(val) = fail_on_revert {
+      opts = opts.fail_on_revert(val);
+    }
+    if let Some(val) = auto_gen_food {
+      opts = opts.auto_gen_food(val);
+    }
+    if let Some(val) = seed {
+      opts = opts.seed(val);
+    }
+    if let Some(val) = players {
+      opts = opts.players(val);
+    }
+    if let Some(val) = walls {
+      opts = opts.walls(from_py_all(val));
+    }
+    if let Some(val) = food {
+      opts = opts.food(from_py_all(val));
+    }
+    if let Some(val) = growth_per_food {
+      opts = opts.growth_per_food(val);
+    }
+    if let Some(val) = input_queue_depth {
+      opts = opts.input_queue_depth(val);
+    }
+    if let Some(val) = wrap_edges {
+      let edges = val
+        .parse::<WrapEdges>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+      opts = opts.wrap_edges(edges);
+    }
+    if let Some(val) = portals {
+      let portals = val
+        .into_iter()
+        .map(|(a, b)| (from_py(a), from_py(b)))
+        .collect();
+      opts = opts.portals(portals);
+    }
+    if let Some(val) = start_head {
+      opts = opts.start_head(from_py(val));
+    }
+    if let Some(val) = start_direction {
+      opts = opts.start_direction(val);
+    }
+    if let Some(val) = start_body {
+      opts = opts.start_body(from_py_all(val));
+    }
+    if let Some(val) = death_rule {
+      opts = opts.death_rule(val);
+    }
+    Ok(PyOptions(opts))
+  }
+}
+
+#[pyclass(name = "SnakeCtrlState", get_all)]
+struct PyState {
+  snake: Vec<PyPoint>,
+  food: Vec<PyPoint>,
+  head_direction: Direction,
+  tail_direction: Direction,
+}
+
+/// One snake segment. `direction` is set for the head and the tail,
+/// `is_vertical` for a straight body segment and `corner` for a corner.
+#[pyclass(name = "SnakePart", get_all)]
+#[derive(Clone)]
+struct PyPart {
+  point: PyPoint,
+  kind: &'static str,
+  direction: Option<Direction>,
+  is_vertical: Option<bool>,
+  corner: Option<SnakeCornerVariant>,
+}
+
+#[pyclass(name = "SnakeCtrlFullState", get_all)]
+struct PyFullState {
+  snake: Vec<PyPart>,
+  food: Vec<PyPoint>,
+  /// One per point of `food`.
+  food_kinds: Vec<FoodKind>,
+  direction: Direction,
+}
+
+/// What happened to the first player during a tick.
+#[pyclass(name = "Tick", get_all)]
+struct PyTick {
+  head: PyPoint,
+  length: usize,
+  eaten: Option<PyPoint>,
+  eaten_kind: Option<FoodKind>,
+  spawned: Option<PyPoint>,
+  death_cause: Option<DeathCause>,
+  is_teleported: bool,
+  is_won: bool,
+  is_over: bool,
+}
+
+impl PyTick {
+  fn new(ctrl: &SnakeCtrl, outcome: &TickOutcome) -> Self {
+    let snake = &ctrl.board.snakes[0].body;
+    PyTick {
+      head: to_py(snake[0]),
+      length: snake.len(),
+      eaten: outcome.eaten_by(0).map(to_py),
+      eaten_kind: outcome.eaten_kind_by(0),
+      spawned: outcome.spawned_food().map(to_py),
+      death_cause: outcome.death_of(0).map(|(cause, _)| cause),
+      is_teleported: outcome.is_teleported(),
+      is_won: outcome.winner() == Some(0),
+      is_over: outcome.is_over(),
+    }
+  }
+}
+
+/// Fills a C-contiguous, read-only view over `data` laid out as `shape`.
+/// `shape` and `strides` live in `owner`, which the view keeps alive.
+///
+/// # Safety
+/// `view` must come from the buffer protocol, `data`, `shape` and
+/// `strides` must be owned by the frozen `owner`.
+#[allow(clippy::too_many_arguments)]
+unsafe fn fill_view(
+  view: *mut ffi::Py_buffer,
+  flags: c_int,
+  data: *const c_void,
+  item_size: usize,
+  format: &'static CStr,
+  shape: &[isize],
+  strides: &[isize],
+  owner: Bound<'_, PyAny>,
+) -> PyResult<()> {
+  if view.is_null() {
+    return Err(PyBufferError::new_err("the view is null"));
+  }
+  if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE {
+    return Err(PyBufferError::new_err("the buffer is read-only"));
+  }
+  let is_nd = flags & ffi::PyBUF_ND == ffi::PyBUF_ND;
+  let view = &mut *view;
+  view.obj = owner.into_ptr();
+  view.buf = data as *mut c_void;
+  view.len = shape.iter().product::<isize>() * item_size as isize;
+  view.readonly = 1;
+  view.itemsize = item_size as isize;
+  view.format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+    format.as_ptr() as *mut _
+  } else {
+    ptr::null_mut()
+  };
+  view.ndim = if is_nd { shape.len() as c_int } else { 1 };
+  view.shape = if is_nd {
+    shape.as_ptr() as *mut _
+  } else {
+    ptr::null_mut()
+  };
+  view.strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+    strides.as_ptr() as *mut _
+  } else {
+    ptr::null_mut()
+  };
+  view.suboffsets = ptr::null_mut();
+  view.internal = ptr::null_mut();
+  Ok(())
+}
+
+/// Row-major strides of a contiguous array.
+fn strides_of(shape: &[isize], item_size: usize) -> Vec<isize> {
+  let mut strides = vec![item_size as isize; shape.len()];
+  for i in (0..shape.len().saturating_sub(1)).rev() {
+    strides[i] = strides[i + 1] * shape[i + 1];
+  }
+  strides
+}
+
+/// The board as `Cell::code`s of shape `(dimension_y, dimension_x)`, row
+/// `y = 0` first. `numpy.asarray(matrix)` gives an `uint8` array.
+#[pyclass(name = "Matrix", frozen)]
+struct PyMatrix {
+  matrix: SnakeCtrlMatrix,
+  codes: Vec<u8>,
+  shape: [isize; 2],
+  strides: Vec<isize>,
+}
+
+impl PyMatrix {
+  fn new(matrix: SnakeCtrlMatrix) -> Self {
+    let (dim_x, dim_y) = matrix.dimensions();
+    let shape = [dim_y as isize, dim_x as isize];
+    PyMatrix {
+      codes: matrix.to_codes(),
+      strides: strides_of(&shape, 1),
+      matrix,
+      shape,
+    }
+  }
+}
+
+#[pymethods]
+impl PyMatrix {
+  /// `(dimension_x, dimension_y)`.
+  fn dimensions(&self) -> (u16, u16) {
+    self.matrix.dimensions()
+  }
+
+  /// `Cell::code` of the cell.
+  fn get(&self, x: u16, y: u16) -> PyResult<u8> {
+    Ok(self.matrix.get(x, y)?.code())
+  }
+
+  /// `Cell::COUNT` one-hot planes of the cells.
+  fn one_hot(&self) -> PyPlanes {
+    let (dim_x, dim_y) = self.matrix.dimensions();
+    let shape = [Cell::COUNT as isize, dim_y as isize, dim_x as isize];
+    PyPlanes {
+      values: self.matrix.one_hot(),
+      strides: strides_of(&shape, 4),
+      shape,
+    }
+  }
+
+  /// Text with the highest `y` on top.
+  #[pyo3(signature = (ascii = false))]
+  fn render(&self, ascii: bool) -> String {
+    let glyphs = if ascii {
+      Glyphs::ascii()
+    } else {
+      Glyphs::unicode()
+    };
+    self.matrix.render(&glyphs)
+  }
+
+  fn __str__(&self) -> String {
+    self.matrix.render(&Glyphs::default())
+  }
+
+  unsafe fn __getbuffer__(
+    slf: Bound<'_, Self>,
+    view: *mut ffi::Py_buffer,
+    flags: c_int,
+  ) -> PyResult<()> {
+    let this = slf.get();
+    fill_view(
+      view,
+      flags,
+      this.codes.as_ptr() as *const c_void,
+      1,
+      ffi::c_str!("B"),
+      &this.shape,
+      &this.strides,
+      slf.clone().into_any(),
+    )
+  }
+
+  unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
+
+/// `float32` values of shape `(Cell::COUNT, dimension_y, dimension_x)`,
+/// plane `i` marks the cells of index `i`.
+#[pyclass(name = "Planes", frozen)]
+struct PyPlanes {
+  values: Vec<f32>,
+  shape: [isize; 3],
+  strides: Vec<isize>,
+}
+
+#[pymethods]
+impl PyPlanes {
+  unsafe fn __getbuffer__(
+    slf: Bound<'_, Self>,
+    view: *mut ffi::Py_buffer,
+    flags: c_int,
+  ) -> PyResult<()> {
+    let this = slf.get();
+    fill_view(
+      view,
+      flags,
+      this.values.as_ptr() as *const c_void,
+      4,
+      ffi::c_str!("f"),
+      &this.shape,
+      &this.strides,
+      slf.clone().into_any(),
+    )
+  }
+
+  unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
+
+#[pyclass(name = "SnakeCtrl")]
+struct PySnakeCtrl(SnakeCtrl);
+
+#[pymethods]
+impl PySnakeCtrl {
+  #[new]
+  #[pyo3(signature = (options = None))]
+  fn new(options: Option<PyOptions>) -> PyResult<Self> {
+    let opts = options.unwrap_or_default();
+    Ok(PySnakeCtrl(SnakeCtrl::new(&opts.0)?))
+  }
+
+  fn direction_to(&mut self, direction: Direction) -> PyResult<()> {
+    Ok(self.0.direction_to(direction)?)
+  }
+
+  fn direction_to_player(
+    &mut self,
+    player: usize,
+    direction: Direction,
+  ) -> PyResult<()> {
+    Ok(self.0.direction_to_player(player, direction)?)
+  }
+
+  fn current_direction(&self) -> Direction {
+    self.0.current_direction()
+  }
+
+  fn next_tick(&mut self) -> PyResult<PyTick> {
+    let outcome = self.0.next_tick()?;
+    Ok(PyTick::new(&self.0, &outcome))
+  }
+
+  fn is_over(&self) -> bool {
+    self.0.is_over()
+  }
+
+  fn ticks(&self) -> u64 {
+    self.0.ticks()
+  }
+
+  #[pyo3(signature = (seed = None))]
+  fn restart(&mut self, seed: Option<u64>) -> PyResult<()> {
+    match seed {
+      Some(seed) => Ok(self.0.restart_with_seed(seed)?),
+      None => Ok(self.0.restart()?),
+    }
+  }
+
+  fn seed(&self) -> u64 {
+    self.0.seed()
+  }
+
+  /// `(dimension_x, dimension_y)`.
+  fn dimensions(&self) -> (u16, u16) {
+    self.0.dimensions()
+  }
+
+  fn walls(&self) -> Vec<PyPoint> {
+    self.0.walls().iter().copied().map(to_py).collect()
+  }
+
+  #[pyo3(signature = (reversed_y = false))]
+  fn get_state(&self, reversed_y: bool) -> PyState {
+    let state = if reversed_y {
+      self.0.get_state_reversed_y()
+    } else {
+      self.0.get_state()
+    };
+    PyState {
+      snake: state.snake.into_iter().map(to_py).collect(),
+      food: state.food.into_iter().map(to_py).collect(),
+      head_direction: state.head_direction,
+      tail_direction: state.tail_direction,
+    }
+  }
+
+  #[pyo3(signature = (reversed_y = false))]
+  fn get_full_state(&self, reversed_y: bool) -> PyFullState {
+    let state = if reversed_y {
+      self.0.get_full_state_reversed_y()
+    } else {
+      self.0.get_full_state()
+    };
+    let snake = state
+      .snake
+      .into_iter()
+      .map(|part| {
+        let mut py_part = PyPart {
+          point: to_py(part.point),
+          kind: "",
+          direction: None,
+          is_vertical: None,
+          corner: None,
+        };
+        match part.variant {
+          SnakePartVariant::Head(d) => {
+            py_part.kind = "head";
+            py_part.direction = Some(d);
+          }
+          SnakePartVariant::Tail(d) => {
+            py_part.kind = "tail";
+            py_part.direction = Some(d);
+          }
+          SnakePartVariant::Body(is_vertical) => {
+            py_part.kind = "body";
+            py_part.is_vertical = Some(is_vertical);
+          }
+          SnakePartVariant::Corner(corner) => {
+            py_part.kind = "corner";
+            py_part.corner = Some(corner);
+          }
+        }
+        py_part
+      })
+      .collect();
+    PyFullState {
+      snake,
+      food: state.food.iter().map(|f| to_py(f.point)).collect(),
+      food_kinds: state.food.iter().map(|f| f.kind).collect(),
+      direction: state.direction,
+    }
+  }
+
+  fn get_matrix(&self) -> PyMatrix {
+    PyMatrix::new(self.0.get_matrix())
+  }
+}
+
+/// The `snake_ctrl` Python module.
+#[pymodule]
+pub fn snake_ctrl(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_class::<PySnakeCtrl>()?;
+  m.add_class::<PyOptions>()?;
+  m.add_class::<Direction>()?;
+  m.add_class::<DeathCause>()?;
+  m.add_class::<FoodKind>()?;
+  m.add_class::<SnakeCornerVariant>()?;
+  m.add_class::<DeathRule>()?;
+  m.add_class::<PyMatrix>()?;
+  m.add_class::<PyPlanes>()?;
+  m.add_class::<PyState>()?;
+  m.add_class::<PyFullState>()?;
+  m.add_class::<PyPart>()?;
+  m.add_class::<PyTick>()?;
+  m.add("SnakeCtrlError", m.py().get_type::<SnakeCtrlError>())?;
+  Ok(())
+}
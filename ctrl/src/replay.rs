@@ -0,0 +1,102 @@
+use crate::options::InnerCfg;
+use crate::{Direction, SnakeCtrl, SnakeCtrlResult, TickOutcome};
+
+/// A `direction_to_player` call made before the given tick.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReplayInput {
+  pub tick: u64,
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub player: usize,
+  pub direction: Direction,
+}
+
+/// A recorded game: the options with the seed it started from, followed by
+/// the inputs. Created by `SnakeCtrl::start_recording` and
+/// `SnakeCtrl::take_recording`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+  cfg: InnerCfg,
+  inputs: Vec<ReplayInput>,
+  ticks: u64,
+}
+
+impl Replay {
+  pub(crate) fn new(cfg: InnerCfg) -> Self {
+    Replay {
+      cfg,
+      inputs: vec![],
+      ticks: 0,
+    }
+  }
+
+  pub(crate) fn push(
+    &mut self,
+    tick: u64,
+    player: usize,
+    direction: Direction,
+  ) {
+    self.inputs.push(ReplayInput {
+      tick,
+      player,
+      direction,
+    });
+  }
+
+  pub(crate) fn set_ticks(&mut self, ticks: u64) {
+    self.ticks = ticks;
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.cfg.seed
+  }
+
+  pub fn inputs(&self) -> &[ReplayInput] {
+    &self.inputs
+  }
+
+  /// Length of the recorded game in ticks.
+  pub fn ticks(&self) -> u64 {
+    self.ticks
+  }
+
+  /// A fresh game in the state the recording started from.
+  pub fn new_ctrl(&self) -> SnakeCtrlResult<SnakeCtrl> {
+    SnakeCtrl::from_cfg(self.cfg.clone())
+  }
+}
+
+/// Feeds a `Replay` into a `SnakeCtrl` created by `Replay::new_ctrl`, one
+/// tick at a time.
+pub struct ReplayPlayer {
+  replay: Replay,
+  cursor: usize,
+}
+
+impl ReplayPlayer {
+  pub fn new(replay: Replay) -> Self {
+    ReplayPlayer { replay, cursor: 0 }
+  }
+
+  pub fn replay(&self) -> &Replay {
+    &self.replay
+  }
+
+  pub fn is_finished(&self, ctrl: &SnakeCtrl) -> bool {
+    ctrl.is_over() || ctrl.ticks() >= self.replay.ticks
+  }
+
+  /// Applies the inputs recorded for the current tick and moves on.
+  pub fn step(&mut self, ctrl: &mut SnakeCtrl) -> SnakeCtrlResult<TickOutcome> {
+    let tick = ctrl.ticks();
+    while let Some(input) = self.replay.inputs.get(self.cursor) {
+      if input.tick > tick {
+        break;
+      }
+      ctrl.direction_to_player(input.player, input.direction)?;
+      self.cursor += 1;
+    }
+    ctrl.next_tick()
+  }
+}
@@ -0,0 +1,234 @@
+use crate::{
+  DeathCause, Direction, Point, SnakeCtrl, SnakeCtrlOptions, SnakeCtrlResult,
+  WrapEdges,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// What a `Policy` sees before every tick.
+#[derive(Clone, Debug)]
+pub struct Observation {
+  pub snake: Vec<Point>,
+  /// Bodies of the other alive snakes.
+  pub others: Vec<Point>,
+  pub walls: Vec<Point>,
+  /// Food worth eating, poison is left out.
+  pub food: Vec<Point>,
+  pub poison: Vec<Point>,
+  pub direction: Direction,
+  pub dimension_x: u16,
+  pub dimension_y: u16,
+  pub wrap: WrapEdges,
+  /// Portal cells, the autopilots keep away from them.
+  pub portals: Vec<Point>,
+  pub tick: u64,
+}
+
+impl Observation {
+  pub fn head(&self) -> Point {
+    self.snake[0]
+  }
+
+  /// Cells the snake must not enter besides its own body.
+  pub fn obstacles(&self) -> Vec<Point> {
+    self
+      .others
+      .iter()
+      .chain(&self.walls)
+      .chain(&self.poison)
+      .chain(&self.portals)
+      .copied()
+      .collect()
+  }
+}
+
+pub trait Policy {
+  fn decide(&mut self, obs: &Observation) -> Direction;
+}
+
+impl<F: FnMut(&Observation) -> Direction> Policy for F {
+  fn decide(&mut self, obs: &Observation) -> Direction {
+    self(obs)
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameStats {
+  pub seed: u64,
+  /// Number of eaten food.
+  pub score: u64,
+  pub length: usize,
+  pub ticks: u64,
+  /// `None` when the snake won or ran out of ticks.
+  pub death: Option<DeathCause>,
+  pub is_won: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimSummary {
+  pub games: usize,
+  pub mean_score: f64,
+  pub max_score: u64,
+  pub mean_length: f64,
+  pub mean_ticks: f64,
+  pub wins: usize,
+  pub ate_itself: usize,
+  pub hit_the_wall: usize,
+  pub hit_snake: usize,
+  pub hit_obstacle: usize,
+  pub poisoned: usize,
+  pub timed_out: usize,
+}
+
+impl SimSummary {
+  pub fn from_stats(stats: &[GameStats]) -> Self {
+    let mut summary = SimSummary {
+      games: stats.len(),
+      ..SimSummary::default()
+    };
+    if stats.is_empty() {
+      return summary;
+    }
+
+    let games = stats.len() as f64;
+    for s in stats {
+      summary.mean_score += s.score as f64 / games;
+      summary.mean_length += s.length as f64 / games;
+      summary.mean_ticks += s.ticks as f64 / games;
+      summary.max_score = summary.max_score.max(s.score);
+      match s.death {
+        Some(DeathCause::AteItself) => summary.ate_itself += 1,
+        Some(DeathCause::HitTheWall) => summary.hit_the_wall += 1,
+        Some(DeathCause::HitSnake | DeathCause::HeadToHead) => {
+          summary.hit_snake += 1
+        }
+        Some(DeathCause::HitObstacle) => summary.hit_obstacle += 1,
+        Some(DeathCause::Poisoned) => summary.poisoned += 1,
+        None if s.is_won => summary.wins += 1,
+        None => summary.timed_out += 1,
+      }
+    }
+    summary
+  }
+}
+
+/// Where `SnakeCtrl::simulate` got the first player.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rollout {
+  /// Ticks played, fewer than the moves when the game ended on the way.
+  pub ticks: usize,
+  /// Score gained on the way.
+  pub score: u64,
+  pub length: usize,
+  pub death: Option<DeathCause>,
+  pub is_over: bool,
+}
+
+static DEF_GAMES: usize = 100;
+static DEF_MAX_TICKS: u64 = 100_000;
+
+/// Runs many headless games in parallel. Game `i` is seeded with
+/// `seed + i`, so a batch is reproducible.
+pub struct Simulator {
+  opts: SnakeCtrlOptions,
+  games: usize,
+  threads: usize,
+  max_ticks: u64,
+  seed: u64,
+}
+
+impl Simulator {
+  pub fn new(opts: &SnakeCtrlOptions) -> Self {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    Simulator {
+      opts: opts.clone(),
+      games: DEF_GAMES,
+      threads,
+      max_ticks: DEF_MAX_TICKS,
+      seed: 0,
+    }
+  }
+  pub fn games(mut self, val: usize) -> Self {
+    self.games = val;
+    self
+  }
+  pub fn threads(mut self, val: usize) -> Self {
+    self.threads = val.max(1);
+    self
+  }
+  /// Games that last longer are stopped and reported without a death.
+  pub fn max_ticks(mut self, val: u64) -> Self {
+    self.max_ticks = val;
+    self
+  }
+  pub fn seed(mut self, val: u64) -> Self {
+    self.seed = val;
+    self
+  }
+
+  /// Plays every game with a policy made by `make_policy(game_index)` and
+  /// returns the stats in game order.
+  pub fn run<F>(&self, make_policy: F) -> SnakeCtrlResult<Vec<GameStats>>
+  where
+    F: Fn(usize) -> Box<dyn Policy> + Sync,
+  {
+    let next_game = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(self.games));
+
+    thread::scope(|scope| {
+      for _ in 0..self.threads.min(self.games) {
+        scope.spawn(|| loop {
+          let ind = next_game.fetch_add(1, Ordering::Relaxed);
+          if ind >= self.games {
+            break;
+          }
+          let stats = self.play(ind, make_policy(ind).as_mut());
+          results.lock().unwrap().push((ind, stats));
+        });
+      }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(ind, _)| *ind);
+    results.into_iter().map(|(_, stats)| stats).collect()
+  }
+
+  /// Plays a single game on the current thread.
+  pub fn play(
+    &self,
+    game_index: usize,
+    policy: &mut dyn Policy,
+  ) -> SnakeCtrlResult<GameStats> {
+    let seed = self.seed.wrapping_add(game_index as u64);
+    let mut ctrl = SnakeCtrl::new(&self.opts.clone().seed(seed))?;
+    let mut stats = GameStats {
+      seed,
+      score: 0,
+      length: 0,
+      ticks: 0,
+      death: None,
+      is_won: false,
+    };
+
+    while ctrl.ticks() < self.max_ticks {
+      let direction = policy.decide(&ctrl.observe());
+      ctrl.direction_to(direction)?;
+      let outcome = ctrl.next_tick()?;
+      if outcome.eaten_by(0).is_some() {
+        stats.score += 1;
+      }
+      if let Some((cause, _)) = outcome.death_of(0) {
+        stats.death = Some(cause);
+      }
+      if outcome.is_over() {
+        stats.is_won = outcome.is_won();
+        break;
+      }
+    }
+
+    stats.length = ctrl.board.snakes[0].body.len();
+    stats.ticks = ctrl.ticks();
+    Ok(stats)
+  }
+}
@@ -0,0 +1,88 @@
+use crate::board::{Board, Snake};
+use crate::observer::Observers;
+use crate::options::InnerCfg;
+use crate::{Food, SnakeCtrl, SnakeCtrlErr, SnakeCtrlResult};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+static SNAPSHOT_VERSION: u32 = 3;
+
+/// Everything needed to resume a game: the snakes with their directions
+/// and scores, the food, the config and the RNG state.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SnakeCtrlSnapshot {
+  version: u32,
+  cfg: InnerCfg,
+  snakes: Vec<Snake>,
+  food: Vec<Food>,
+  is_over: bool,
+  tick: u64,
+  seed: u64,
+  rng: Pcg32,
+}
+
+impl SnakeCtrlSnapshot {
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn dimensions(&self) -> (u16, u16) {
+    (self.cfg.dimension_x, self.cfg.dimension_y)
+  }
+
+  pub(crate) fn of(ctrl: &SnakeCtrl) -> Self {
+    SnakeCtrlSnapshot {
+      version: SNAPSHOT_VERSION,
+      cfg: (*ctrl.cfg).clone(),
+      snakes: ctrl.board.snakes.clone(),
+      food: ctrl.board.food.clone(),
+      is_over: ctrl.is_over,
+      tick: ctrl.tick,
+      seed: ctrl.board.seed(),
+      rng: ctrl.board.rng().clone(),
+    }
+  }
+
+  /// Checks the saved parts before building a game out of them. The config
+  /// gets the limits of `SnakeCtrlOptions` again.
+  pub(crate) fn restore(self) -> SnakeCtrlResult<SnakeCtrl> {
+    if self.version != SNAPSHOT_VERSION {
+      return Err(SnakeCtrlErr::SnapshotVersion {
+        found: self.version,
+        expected: SNAPSHOT_VERSION,
+      });
+    }
+    if self.snakes.is_empty() || self.snakes.iter().any(|s| s.body.len() < 2) {
+      return Err(SnakeCtrlErr::SnakeIsZero);
+    }
+    let snake_points = self.snakes.iter().flat_map(|s| s.body.iter());
+    let food_points = self.food.iter().map(|f| &f.point);
+    for p in snake_points.chain(food_points) {
+      if p.0 >= self.cfg.dimension_x {
+        return Err(SnakeCtrlErr::ColumnIndexOutOfBounds(p.0));
+      }
+      if p.1 >= self.cfg.dimension_y {
+        return Err(SnakeCtrlErr::RowIndexOutOfBounds(p.1));
+      }
+    }
+
+    let cfg = Arc::new(self.cfg.with_limits());
+    let board = Board::from_parts(
+      cfg.clone(),
+      self.seed,
+      self.rng,
+      self.snakes,
+      self.food,
+    )?;
+
+    Ok(SnakeCtrl {
+      board,
+      cfg,
+      is_over: self.is_over,
+      tick: self.tick,
+      recording: None,
+      observers: Observers::default(),
+    })
+  }
+}
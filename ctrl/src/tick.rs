@@ -0,0 +1,147 @@
+use crate::{FoodKind, Point};
+
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "pyo3", pyo3::pyclass(eq, eq_int))]
+pub enum DeathCause {
+  AteItself,
+  HitTheWall,
+  /// The head ran into the body of another snake.
+  HitSnake,
+  /// Two heads moved into the same cell.
+  HeadToHead,
+  /// The head ran into an obstacle inside the board.
+  HitObstacle,
+  /// The snake ate poison.
+  Poisoned,
+}
+
+/// `snake` fields hold the id of the player the event is about.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TickEvent {
+  /// The head moved from one cell to another.
+  Moved {
+    snake: usize,
+    from: Point,
+    to: Point,
+  },
+  /// The head went through a wall and came out on the opposite side.
+  Teleported {
+    snake: usize,
+    from: Point,
+    to: Point,
+  },
+  /// Food at the given point was eaten.
+  Ate {
+    snake: usize,
+    at: Point,
+    kind: FoodKind,
+  },
+  /// The snake grew, holds the new length.
+  Grew { snake: usize, length: usize },
+  /// The snake got shorter, holds the new length.
+  Shrank { snake: usize, length: usize },
+  /// New food appeared at the given point.
+  FoodSpawned(Point),
+  /// Food at the given point was not eaten in time and disappeared.
+  FoodExpired(Point),
+  /// The snake died. `at` is the cell where the collision happened, the
+  /// snake itself is left as it was before the tick.
+  Died {
+    snake: usize,
+    cause: DeathCause,
+    at: Point,
+  },
+  /// The snake covers the whole board or is the last one standing.
+  Won(usize),
+  /// The game ended during this tick.
+  GameOver,
+}
+
+/// Everything that happened during one `SnakeCtrl::next_tick`, in order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TickOutcome {
+  pub events: Vec<TickEvent>,
+}
+
+impl TickOutcome {
+  pub fn is_ate(&self) -> bool {
+    self.eaten().is_some()
+  }
+
+  /// The first food eaten during the tick.
+  pub fn eaten(&self) -> Option<Point> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Ate { at, .. } => Some(*at),
+      _ => None,
+    })
+  }
+
+  /// Food eaten by the given player.
+  pub fn eaten_by(&self, id: usize) -> Option<Point> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Ate { snake, at, .. } if *snake == id => Some(*at),
+      _ => None,
+    })
+  }
+
+  /// Kind of the food eaten by the given player.
+  pub fn eaten_kind_by(&self, id: usize) -> Option<FoodKind> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Ate { snake, kind, .. } if *snake == id => Some(*kind),
+      _ => None,
+    })
+  }
+
+  pub fn is_teleported(&self) -> bool {
+    self
+      .events
+      .iter()
+      .any(|e| matches!(e, TickEvent::Teleported { .. }))
+  }
+
+  pub fn spawned_food(&self) -> Option<Point> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::FoodSpawned(p) => Some(*p),
+      _ => None,
+    })
+  }
+
+  /// The first death during the tick.
+  pub fn death(&self) -> Option<(DeathCause, Point)> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Died { cause, at, .. } => Some((*cause, *at)),
+      _ => None,
+    })
+  }
+
+  /// Death of the given player.
+  pub fn death_of(&self, id: usize) -> Option<(DeathCause, Point)> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Died { snake, cause, at } if *snake == id => {
+        Some((*cause, *at))
+      }
+      _ => None,
+    })
+  }
+
+  pub fn is_dead(&self) -> bool {
+    self.death().is_some()
+  }
+
+  pub fn winner(&self) -> Option<usize> {
+    self.events.iter().find_map(|e| match e {
+      TickEvent::Won(id) => Some(*id),
+      _ => None,
+    })
+  }
+
+  pub fn is_won(&self) -> bool {
+    self.winner().is_some()
+  }
+
+  /// Whether the game ended during this tick.
+  pub fn is_over(&self) -> bool {
+    self.events.contains(&TickEvent::GameOver)
+  }
+}
@@ -0,0 +1,179 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "snake_ctrl.h"
+
+#define CHECK(expr)                                                          \
+  do {                                                                       \
+    if (!(expr)) {                                                           \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,      \
+              #expr);                                                        \
+      return 1;                                                              \
+    }                                                                        \
+  } while (0)
+
+static int test_lifecycle(void) {
+  SnakeCtrlCOptions opts = snake_ctrl_options_default();
+  opts.dimension_x = 15;
+  opts.dimension_y = 10;
+  opts.initial_snake_size = 4;
+  opts.use_seed = true;
+  opts.seed = 7;
+
+  SnakeCtrl *ctrl = NULL;
+  CHECK(snake_ctrl_new(&opts, &ctrl) == SnakeCtrlCode_Ok);
+  CHECK(ctrl != NULL);
+
+  uint64_t seed = 0;
+  CHECK(snake_ctrl_seed(ctrl, &seed) == SnakeCtrlCode_Ok);
+  CHECK(seed == 7);
+
+  CHECK(snake_ctrl_direction_to(ctrl, Direction_Top) == SnakeCtrlCode_Ok);
+  SnakeCtrlCTick tick;
+  CHECK(snake_ctrl_next_tick(ctrl, &tick) == SnakeCtrlCode_Ok);
+  CHECK(tick.head._0 == 7 && tick.head._1 == 6);
+  CHECK(tick.length == 4);
+  CHECK(!tick.is_dead && !tick.is_won);
+
+  Direction dir;
+  CHECK(snake_ctrl_current_direction(ctrl, &dir) == SnakeCtrlCode_Ok);
+  CHECK(dir == Direction_Top);
+
+  SnakeCtrlCState state;
+  CHECK(snake_ctrl_get_state(ctrl, false, &state) == SnakeCtrlCode_Ok);
+  CHECK(state.snake_len == 4);
+  CHECK(state.snake[0]._0 == 7 && state.snake[0]._1 == 6);
+  CHECK(state.head_direction == Direction_Top);
+  snake_ctrl_state_free(&state);
+  CHECK(state.snake == NULL);
+
+  SnakeCtrlCFullState full;
+  CHECK(snake_ctrl_get_full_state(ctrl, false, &full) == SnakeCtrlCode_Ok);
+  CHECK(full.snake_len == 4);
+  CHECK(full.snake[0].kind == SnakeCtrlCPartKind_Head);
+  CHECK(full.snake[1].kind == SnakeCtrlCPartKind_Corner);
+  CHECK(full.snake[3].kind == SnakeCtrlCPartKind_Tail);
+  snake_ctrl_full_state_free(&full);
+
+  uint16_t dim_x = 0, dim_y = 0;
+  CHECK(snake_ctrl_get_matrix(ctrl, NULL, 0, &dim_x, &dim_y) ==
+        SnakeCtrlCode_Ok);
+  CHECK(dim_x == 15 && dim_y == 10);
+  uint8_t *cells = malloc((size_t)dim_x * dim_y);
+  CHECK(snake_ctrl_get_matrix(ctrl, cells, (size_t)dim_x * dim_y, NULL,
+                              NULL) == SnakeCtrlCode_Ok);
+  int snake_cells = 0;
+  for (size_t i = 0; i < (size_t)dim_x * dim_y; i++) {
+    if (cells[i] == 1) {
+      snake_cells++;
+    }
+  }
+  free(cells);
+  CHECK(snake_cells == 4);
+
+  CHECK(snake_ctrl_restart(ctrl) == SnakeCtrlCode_Ok);
+  CHECK(snake_ctrl_current_direction(ctrl, &dir) == SnakeCtrlCode_Ok);
+  CHECK(dir == Direction_Right);
+  CHECK(snake_ctrl_seed(ctrl, &seed) == SnakeCtrlCode_Ok);
+  CHECK(seed == 8);
+
+  snake_ctrl_free(ctrl);
+  return 0;
+}
+
+static int test_errors(void) {
+  SnakeCtrlCOptions opts = snake_ctrl_options_default();
+  opts.walking_through_the_walls = false;
+  opts.auto_gen_food = false;
+
+  SnakeCtrl *ctrl = NULL;
+  CHECK(snake_ctrl_new(&opts, &ctrl) == SnakeCtrlCode_Ok);
+
+  SnakeCtrlCTick tick = {0};
+  for (int i = 0; i < 10 && !tick.is_dead; i++) {
+    CHECK(snake_ctrl_next_tick(ctrl, &tick) == SnakeCtrlCode_Ok);
+  }
+  CHECK(tick.is_dead);
+  CHECK(tick.death_cause == DeathCause_HitTheWall);
+  CHECK(tick.death_point._0 == 6);
+
+  SnakeCtrlCode code = snake_ctrl_next_tick(ctrl, NULL);
+  CHECK(code == SnakeCtrlCode_GameIsOver);
+  CHECK(snake_ctrl_code_message(code)[0] != '\0');
+  snake_ctrl_free(ctrl);
+
+  opts = snake_ctrl_options_default();
+  opts.initial_snake_size = 100;
+  ctrl = NULL;
+  CHECK(snake_ctrl_new(&opts, &ctrl) == SnakeCtrlCode_InitSnakeSizeIsBig);
+  CHECK(ctrl == NULL);
+
+  CHECK(snake_ctrl_next_tick(NULL, NULL) == SnakeCtrlCode_NullPointer);
+
+  opts = snake_ctrl_options_default();
+  opts.death_rule = 42;
+  CHECK(snake_ctrl_new(&opts, &ctrl) == SnakeCtrlCode_InvalidValue);
+  CHECK(ctrl == NULL);
+  CHECK(snake_ctrl_new(NULL, &ctrl) == SnakeCtrlCode_Ok);
+  CHECK(snake_ctrl_direction_to(ctrl, 4) == SnakeCtrlCode_InvalidValue);
+  CHECK(snake_ctrl_direction_to_player(ctrl, 0, 0xffffffffu) ==
+        SnakeCtrlCode_InvalidValue);
+  snake_ctrl_free(ctrl);
+  return 0;
+}
+
+static int test_env(void) {
+  SnakeCtrlCOptions opts = snake_ctrl_options_default();
+  opts.dimension_x = 9;
+  opts.dimension_y = 9;
+  opts.walking_through_the_walls = false;
+  opts.auto_gen_food = false;
+  SnakeCtrlRewards rewards = snake_ctrl_rewards_default();
+  rewards.step = -0.5f;
+
+  SnakeCtrlEnv *env = NULL;
+  CHECK(snake_ctrl_env_new(&opts, 2, NULL, 0, &env) ==
+        SnakeCtrlCode_InvalidValue);
+  CHECK(env == NULL);
+  CHECK(snake_ctrl_env_new(&opts, SnakeCtrlEnvEncoder_Features, &rewards, 0,
+                           &env) == SnakeCtrlCode_Ok);
+  size_t size = 0;
+  CHECK(snake_ctrl_env_observation_size(env, &size) == SnakeCtrlCode_Ok);
+  CHECK(size == 11);
+
+  float obs[11];
+  CHECK(snake_ctrl_env_reset(env, 5, obs, 11) == SnakeCtrlCode_Ok);
+  CHECK(obs[4] == 1.0f);
+  CHECK(snake_ctrl_env_step(env, SnakeCtrlAction_Straight, obs, 4, NULL) ==
+        SnakeCtrlCode_BufferTooSmall);
+  CHECK(snake_ctrl_env_step(env, 3, obs, 11, NULL) ==
+        SnakeCtrlCode_InvalidValue);
+
+  SnakeCtrlCStep step = {0};
+  while (!step.done) {
+    CHECK(snake_ctrl_env_step(env, SnakeCtrlAction_Straight, obs, 11,
+                              &step) == SnakeCtrlCode_Ok);
+  }
+  CHECK(step.is_dead);
+  CHECK(step.death_cause == DeathCause_HitTheWall);
+  CHECK(step.ticks == 5);
+  CHECK(step.reward == -1.5f);
+  CHECK(snake_ctrl_env_step(env, SnakeCtrlAction_Left, NULL, 0, NULL) ==
+        SnakeCtrlCode_GameIsOver);
+  snake_ctrl_env_free(env);
+  return 0;
+}
+
+int main(void) {
+  if (test_lifecycle() != 0) {
+    return 1;
+  }
+  if (test_errors() != 0) {
+    return 1;
+  }
+  if (test_env() != 0) {
+    return 1;
+  }
+  printf("c harness: ok\n");
+  return 0;
+}
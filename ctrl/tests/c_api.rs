@@ -0,0 +1,75 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn target_dir() -> PathBuf {
+  let mut dir = env::current_exe().unwrap();
+  dir.pop();
+  if dir.ends_with("deps") {
+    dir.pop();
+  }
+  dir
+}
+
+/// `cargo build` leaves the archive in the target dir, `cargo test` only in
+/// `deps`. The newest one is the one of this build.
+fn static_lib(target: &Path) -> Option<PathBuf> {
+  let name = "libsnake_ctrl.a";
+  vec![target.join(name), target.join("deps").join(name)]
+    .into_iter()
+    .filter_map(|p| Some((p.metadata().ok()?.modified().ok()?, p)))
+    .max_by_key(|(modified, _)| *modified)
+    .map(|(_, p)| p)
+}
+
+/// Links against libpython as well when the library embeds the `pyo3`
+/// module.
+fn python_libs() -> Vec<String> {
+  if !cfg!(feature = "pyo3") {
+    return vec![];
+  }
+  let output = Command::new("python3-config")
+    .args(["--ldflags", "--embed"])
+    .output()
+    .unwrap_or_else(|e| panic!("{}, `python3-config` is needed", e));
+  assert!(output.status.success(), "`python3-config` failed");
+  let flags = String::from_utf8(output.stdout).unwrap();
+  flags.split_whitespace().map(str::to_owned).collect()
+}
+
+/// Needs a C compiler, `CC` or `cc`. Set `SNAKE_CTRL_SKIP_C_HARNESS` to
+/// skip it where there is none.
+#[test]
+fn c_harness() {
+  if env::var_os("SNAKE_CTRL_SKIP_C_HARNESS").is_some() {
+    eprintln!("skipping C harness, SNAKE_CTRL_SKIP_C_HARNESS is set");
+    return;
+  }
+  let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+  let target = target_dir();
+  let exe = target.join("snake_ctrl_c_harness");
+  let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+  let lib = static_lib(&target).expect("libsnake_ctrl.a is not built");
+
+  let status = Command::new(&compiler)
+    .arg("-std=c99")
+    .arg("-Wall")
+    .arg("-Werror")
+    .arg("-I")
+    .arg(manifest_dir.join("include"))
+    .arg(manifest_dir.join("tests").join("c").join("harness.c"))
+    .arg(lib)
+    .args(python_libs())
+    .args(["-lpthread", "-ldl", "-lm", "-o"])
+    .arg(&exe)
+    .status()
+    .unwrap_or_else(|e| panic!("unable to run `{}`: {}", compiler, e));
+  assert!(status.success(), "failed to compile the C harness");
+
+  let output = Command::new(&exe).output().unwrap();
+  assert!(
+    output.status.success(),
+    "C harness failed:\n{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+}
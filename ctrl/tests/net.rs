@@ -0,0 +1,91 @@
+#![cfg(feature = "net")]
+
+use snake_ctrl::net::{Client, Server, ServerOptions};
+use snake_ctrl::{Direction, SnakeCtrlOptions};
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn wait_for<F: FnMut(&mut Client) -> bool>(client: &mut Client, mut f: F) {
+  let deadline = Instant::now() + Duration::from_secs(5);
+  while !f(client) {
+    assert!(Instant::now() < deadline, "timed out");
+    client.poll().unwrap();
+    thread::sleep(Duration::from_millis(5));
+  }
+}
+
+#[test]
+fn plays_over_localhost() {
+  let game = SnakeCtrlOptions::default()
+    .dimension_x(20)
+    .dimension_y(10)
+    .players(2)
+    .seed(1);
+  let opts = ServerOptions::new(&game).tick_millis(20);
+  let server = Server::bind("127.0.0.1:0", &opts).unwrap().spawn().unwrap();
+  let addr = server.local_addr();
+
+  let mut first = Client::connect(addr, None).unwrap();
+  let mut second = Client::connect(addr, None).unwrap();
+  assert_eq!((first.player(), second.player()), (0, 1));
+  assert!(Client::connect(addr, None).is_err());
+
+  wait_for(&mut first, |c| c.tick() >= 2);
+  first.direction_to(Direction::Top).unwrap();
+  wait_for(&mut first, |c| c.players()[0].direction == Direction::Top);
+  wait_for(&mut second, |c| c.tick() >= 3);
+  assert_eq!(second.players().len(), 2);
+  assert_eq!(second.full_state(0, false).unwrap().snake.len(), 3);
+
+  let turn = if second.players()[1].direction.is_vertical() {
+    Direction::Left
+  } else {
+    Direction::Bottom
+  };
+  second.direction_to(turn).unwrap();
+  wait_for(&mut second, |c| c.players()[1].direction == turn);
+
+  let token = second.token();
+  drop(second);
+  let mut second = Client::connect(addr, Some(token)).unwrap();
+  assert_eq!(second.player(), 1);
+  wait_for(&mut second, |c| c.is_started());
+  let tick = second.tick();
+  wait_for(&mut second, |c| c.tick() > tick);
+  let turn = if second.players()[1].direction.is_vertical() {
+    Direction::Left
+  } else {
+    Direction::Bottom
+  };
+  second.direction_to(turn).unwrap();
+  wait_for(&mut second, |c| c.players()[1].direction == turn);
+
+  second.ping().unwrap();
+  wait_for(&mut second, |c| c.rtt().is_some());
+  server.stop().unwrap();
+}
+
+#[test]
+fn fails_when_the_game_cannot_start() {
+  let game = SnakeCtrlOptions::default()
+    .dimension_x(20)
+    .dimension_y(1)
+    .players(2);
+  let opts = ServerOptions::new(&game).tick_millis(20);
+  let server = Server::bind("127.0.0.1:0", &opts).unwrap().spawn().unwrap();
+  let addr = server.local_addr();
+
+  let _first = Client::connect(addr, None).unwrap();
+  let mut second = Client::connect(addr, None).unwrap();
+  let deadline = Instant::now() + Duration::from_secs(5);
+  let err = loop {
+    if let Err(e) = second.poll() {
+      break e;
+    }
+    assert!(Instant::now() < deadline, "timed out");
+    thread::sleep(Duration::from_millis(5));
+  };
+  assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+  assert!(server.stop().is_err());
+}
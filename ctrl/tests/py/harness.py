@@ -0,0 +1,58 @@
+import snake_ctrl as sc
+
+opts = sc.SnakeCtrlOptions(
+    dimension_x=9,
+    dimension_y=7,
+    walking_through_the_walls=False,
+    auto_gen_food=False,
+    food=[(6, 3)],
+    seed=7,
+)
+game = sc.SnakeCtrl(opts)
+assert game.seed() == 7
+assert game.dimensions() == (9, 7)
+assert game.current_direction() == sc.Direction.Right
+
+tick = game.next_tick()
+assert tick.head == (5, 3) and tick.length == 3
+assert tick.eaten is None and tick.death_cause is None
+tick = game.next_tick()
+assert tick.eaten == (6, 3) and tick.eaten_kind == sc.FoodKind.Apple
+
+state = game.get_state()
+assert state.snake[0] == (6, 3) and len(state.snake) == 4
+assert state.head_direction == sc.Direction.Right
+full = game.get_full_state()
+assert full.snake[0].kind == "head"
+assert full.snake[0].direction == sc.Direction.Right
+assert full.snake[-1].kind == "tail"
+
+matrix = game.get_matrix()
+assert matrix.get(6, 3) == 1 and matrix.get(0, 0) == 0
+view = memoryview(matrix)
+assert view.shape == (7, 9) and view.format == "B" and view.readonly
+assert view[3, 6] == 1 and sum(view.tobytes()) == 4
+planes = memoryview(matrix.one_hot())
+assert planes.shape == (11, 7, 9) and planes.format == "f"
+assert planes[1, 3, 6] == 1.0
+assert "@" in matrix.render(ascii=True)
+
+game.direction_to(sc.Direction.Top)
+while not game.is_over():
+    tick = game.next_tick()
+assert tick.death_cause == sc.DeathCause.HitTheWall
+try:
+    game.next_tick()
+    raise AssertionError("a finished game ticked")
+except sc.SnakeCtrlError:
+    pass
+game.restart(seed=3)
+assert game.ticks() == 0 and game.seed() == 3
+
+try:
+    import numpy
+except ImportError:
+    numpy = None
+if numpy is not None:
+    codes = numpy.asarray(game.get_matrix())
+    assert codes.dtype == numpy.uint8 and codes.shape == (7, 9)
@@ -0,0 +1,23 @@
+//! Runs `tests/py/harness.py` on an embedded interpreter with the module
+//! registered. Built only with the `pyo3` feature.
+
+use pyo3::append_to_inittab;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use snake_ctrl::py::snake_ctrl as module;
+use std::ffi::CString;
+
+#[test]
+fn python_harness() {
+  append_to_inittab!(module);
+  pyo3::prepare_freethreaded_python();
+
+  let script = CString::new(include_str!("py/harness.py")).unwrap();
+  Python::with_gil(|py| {
+    let globals = PyDict::new(py);
+    if let Err(e) = py.run(&script, Some(&globals), None) {
+      e.print(py);
+      panic!("python harness failed: {}", e);
+    }
+  });
+}
@@ -0,0 +1,75 @@
+use crate::levels::CAMPAIGN;
+use snake_ctrl::level::Level;
+use snake_ctrl::Goal;
+use std::fs;
+
+/// Kept in the data dir next to the record.
+static PROGRESS_NAME: &str = "snake_rs_campaign";
+static DEF_GOAL: Goal = Goal::EatApples(10);
+
+/// The bundled levels played one after another.
+pub struct Campaign {
+  levels: Vec<Level>,
+  current: usize,
+}
+
+impl Campaign {
+  /// Parses the levels and goes on from the saved progress.
+  pub fn load() -> Result<Self, String> {
+    let levels = CAMPAIGN
+      .iter()
+      .enumerate()
+      .map(|(ind, src)| {
+        src.parse().map_err(|e| format!("level {}: {}", ind + 1, e))
+      })
+      .collect::<Result<Vec<Level>, String>>()?;
+    let current = dirs::data_dir()
+      .and_then(|dir| fs::read_to_string(dir.join(PROGRESS_NAME)).ok())
+      .and_then(|data| data.trim().parse::<usize>().ok())
+      .filter(|ind| *ind < levels.len())
+      .unwrap_or(0);
+    Ok(Campaign { levels, current })
+  }
+
+  pub fn level(&self) -> &Level {
+    &self.levels[self.current]
+  }
+
+  pub fn goal(&self) -> Goal {
+    self.level().goal().unwrap_or(DEF_GOAL)
+  }
+
+  /// Number of the current level, counting from 1.
+  pub fn number(&self) -> usize {
+    self.current + 1
+  }
+
+  pub fn count(&self) -> usize {
+    self.levels.len()
+  }
+
+  /// The biggest board of the campaign, the window fits it.
+  pub fn dimensions(&self) -> (u16, u16) {
+    self.levels.iter().fold((0, 0), |(x, y), level| {
+      let (dim_x, dim_y) = level.dimensions();
+      (x.max(dim_x), y.max(dim_y))
+    })
+  }
+
+  /// Moves to the next level and saves the progress. Returns `false` when
+  /// the finished level was the last one, the campaign then starts over.
+  pub fn complete_level(&mut self) -> bool {
+    let has_next = self.current + 1 < self.levels.len();
+    self.current = if has_next { self.current + 1 } else { 0 };
+    self.write();
+    has_next
+  }
+
+  fn write(&self) {
+    if let Some(dir) = dirs::data_dir() {
+      fs::write(dir.join(PROGRESS_NAME), self.current.to_string())
+        .map_err(|e| println!("{:?}", e))
+        .ok();
+    }
+  }
+}
@@ -1,2 +1,4 @@
 pub static STEP: f64 = 16.0;
 pub static HALF_STEP: f64 = STEP / 2.0;
+/// Turns pressed quicker than the ticks wait for their turn.
+pub static INPUT_QUEUE_DEPTH: u8 = 3;
@@ -0,0 +1,22 @@
+use snake_ctrl::level::Level;
+use std::fs;
+
+/// Levels shipped with the game, in campaign order.
+pub static CAMPAIGN: &[&str] = &[
+  include_str!("../levels/01_box.txt"),
+  include_str!("../levels/02_pillars.txt"),
+  include_str!("../levels/03_corridors.txt"),
+];
+
+/// Level `N` of the campaign, counting from 1, or a level file.
+pub fn load(arg: &str) -> Result<Level, String> {
+  if let Ok(n) = arg.parse::<usize>() {
+    let src = n
+      .checked_sub(1)
+      .and_then(|ind| CAMPAIGN.get(ind))
+      .ok_or_else(|| format!("the campaign has {} levels", CAMPAIGN.len()))?;
+    return src.parse().map_err(|e| format!("level {}: {}", n, e));
+  }
+  let src = fs::read_to_string(arg).map_err(|e| format!("{}: {}", arg, e))?;
+  src.parse().map_err(|e| format!("{}: {}", arg, e))
+}
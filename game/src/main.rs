@@ -1,6 +1,11 @@
 #![windows_subsystem = "windows"]
+mod campaign;
 pub mod consts;
+mod levels;
+mod online;
 mod record;
+mod replay;
+mod save;
 mod timer;
 mod view;
 
@@ -12,16 +17,30 @@ use glutin_window::GlutinWindow as Window;
 static BOARD_DIM_X: u16 = 27;
 static BOARD_DIM_Y: u16 = 23;
 
-use consts::{HALF_STEP, STEP};
+use consts::{HALF_STEP, INPUT_QUEUE_DEPTH, STEP};
 use graphics::rectangle::Border;
 use graphics::{text, DrawState, Rectangle, Transformed};
 use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, TextureSettings};
 use piston::event_loop::{EventLoop, EventSettings, Events};
 use piston::input::*;
 use piston::window::WindowSettings;
-use snake_ctrl::{Direction, SnakeCtrl, SnakeCtrlErr, SnakeCtrlOptions};
+use snake_ctrl::ai::AiKind;
+use snake_ctrl::{
+  Direction, FoodKind, FoodSpawn, PlayerInfo, Point, Policy, ReplayPlayer,
+  SnakeCtrl, SnakeCtrlFullState, SnakeCtrlOptions, TickEvent,
+};
+use std::path::PathBuf;
 use view::colors;
 
+/// Screens of the campaign shown over the board.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Screen {
+  Playing,
+  LevelIntro,
+  LevelComplete,
+  CampaignComplete,
+}
+
 pub struct App<'a> {
   gl: GlGraphics,
   snake_ctrl: SnakeCtrl,
@@ -31,24 +50,52 @@ pub struct App<'a> {
   timer: timer::Timer,
   record: record::Record,
   is_game_over: bool,
+  winner: Option<usize>,
+  replay: Option<ReplayPlayer>,
+  ai: Option<(AiKind, Box<dyn Policy>)>,
+  online: Option<online::Online>,
+  dims: (u16, u16),
+  options: SnakeCtrlOptions,
+  level_speed: Option<u128>,
+  campaign: Option<campaign::Campaign>,
+  screen: Screen,
   def_draw_state: DrawState,
 }
 
 impl<'a> App<'a> {
   fn handle_key_press(&mut self, key: Key) {
-    match key {
-      Key::Right => {
-        self.snake_ctrl.direction_to(Direction::Right).unwrap();
-      }
-      Key::Left => {
-        self.snake_ctrl.direction_to(Direction::Left).unwrap();
-      }
-      Key::Up => {
-        self.snake_ctrl.direction_to(Direction::Top).unwrap();
+    if self.replay.is_some() {
+      self.handle_replay_key_press(key);
+      return;
+    }
+    if let Some(online) = &mut self.online {
+      online.handle_key_press(key);
+      return;
+    }
+    if self.campaign.is_some() {
+      let is_paused_by_screen =
+        self.is_game_over || self.screen != Screen::Playing;
+      if key == Key::Space && is_paused_by_screen {
+        self.next_campaign_screen();
+        return;
       }
-      Key::Down => {
-        self.snake_ctrl.direction_to(Direction::Bottom).unwrap();
+      if let Key::I | Key::M = key {
+        return;
       }
+    }
+    if let Key::Right | Key::Left | Key::Up | Key::Down = key {
+      self.ai = None;
+    }
+    let second = self.is_two_players();
+    match key {
+      Key::Right => self.turn(0, Direction::Right),
+      Key::Left => self.turn(0, Direction::Left),
+      Key::Up => self.turn(0, Direction::Top),
+      Key::Down => self.turn(0, Direction::Bottom),
+      Key::D if second => self.turn(1, Direction::Right),
+      Key::A if second => self.turn(1, Direction::Left),
+      Key::W if second => self.turn(1, Direction::Top),
+      Key::S if second => self.turn(1, Direction::Bottom),
       Key::Space => {
         if self.is_game_over {
           self.restart_game();
@@ -56,31 +103,147 @@ impl<'a> App<'a> {
           self.timer.toggle_pause();
         }
       }
+      Key::I => self.switch_ai(),
+      Key::M => self.switch_players(),
+      _ => {}
+    }
+  }
+
+  fn turn(&mut self, player: usize, direction: Direction) {
+    self
+      .snake_ctrl
+      .direction_to_player(player, direction)
+      .unwrap();
+  }
+
+  fn is_two_players(&self) -> bool {
+    self.snake_ctrl.players().len() > 1
+  }
+
+  /// Starts a new game with one player more or less. The second player
+  /// uses WASD.
+  fn switch_players(&mut self) {
+    let players = if self.is_two_players() { 1 } else { 2 };
+    match SnakeCtrl::new(&self.options.clone().players(players)) {
+      Ok(ctrl) => {
+        self.snake_ctrl = ctrl;
+        self.restart_game();
+      }
+      Err(e) => eprintln!("{}", e),
+    }
+  }
+
+  /// Hands control to the next AI, or back to the player after the last
+  /// one.
+  fn switch_ai(&mut self) {
+    let next = match self.ai.as_ref().map(|(kind, _)| *kind) {
+      None => Some(AiKind::Greedy),
+      Some(AiKind::Greedy) => Some(AiKind::PathFinder),
+      Some(AiKind::PathFinder) => Some(AiKind::Hamiltonian),
+      Some(AiKind::Hamiltonian) => None,
+    };
+    self.ai = next.map(|kind| (kind, kind.policy()));
+  }
+
+  /// Starts the level after its intro, any other screen leads to the intro
+  /// of the level to play next.
+  fn next_campaign_screen(&mut self) {
+    if self.screen == Screen::LevelIntro {
+      self.screen = Screen::Playing;
+      self.timer.resume();
+    } else {
+      self.start_level();
+    }
+  }
+
+  /// Loads the current level of the campaign and shows its intro.
+  fn start_level(&mut self) {
+    let level = match &self.campaign {
+      Some(campaign) => campaign.level().clone(),
+      None => return,
+    };
+    let options = level.options().clone().input_queue_depth(INPUT_QUEUE_DEPTH);
+    match SnakeCtrl::new(&options) {
+      Ok(ctrl) => {
+        self.snake_ctrl = ctrl;
+        self.options = options;
+        self.level_speed = level.speed().map(u128::from);
+        self.dims = level.dimensions();
+        self.restart_game();
+        self.timer.pause();
+        self.screen = Screen::LevelIntro;
+      }
+      Err(e) => eprintln!("{}", e),
+    }
+  }
+
+  fn handle_replay_key_press(&mut self, key: Key) {
+    match key {
+      Key::Space => self.timer.toggle_pause(),
+      Key::Right => {
+        if self.timer.is_paused() {
+          self.update_replay();
+        }
+      }
+      Key::Up => {
+        let speed = self.timer.get_speed();
+        self.timer.set_speed((speed / 2).max(10));
+      }
+      Key::Down => {
+        let speed = self.timer.get_speed();
+        self.timer.set_speed((speed * 2).min(2000));
+      }
       _ => {}
     }
   }
 
   fn restart_game(&mut self) {
     self.score = 0;
-    self.timer = timer::Timer::new(200);
+    self.timer = timer::Timer::new(self.level_speed.unwrap_or(200));
     self.snake_ctrl.restart().unwrap();
+    self.snake_ctrl.start_recording();
     self.is_game_over = false;
+    self.winner = None;
+    self.ai = self.ai.take().map(|(kind, _)| (kind, kind.policy()));
   }
 
   fn render(&mut self, args: &RenderArgs) {
     let viewport = args.viewport();
     let win_width = viewport.window_size[0];
     let win_height = viewport.window_size[1];
+    let (players, states) = self.frame();
+    let walls = self.walls();
+    let portals = self.portals();
+    let overlay = self.overlay(players.len());
     let glyphs = &mut self.glyph_cache;
     let def_draw_state = &self.def_draw_state;
-    let score = self.score;
-    let is_record = self.record.score < score;
+    let score_text = if players.len() > 1 {
+      let scores: Vec<String> = players
+        .iter()
+        .map(|p| format!("P{}: {}", p.id + 1, p.score))
+        .collect();
+      scores.join("   ")
+    } else {
+      format!("Score: {}", self.score)
+    };
+    let is_record = self.online.is_none()
+      && players.len() == 1
+      && self.record.score < self.score;
+    let ai_name = self.ai.as_ref().map(|(kind, _)| kind.name());
+    let top_right = match (&self.online, &self.campaign) {
+      (Some(online), _) => online.status(),
+      (None, Some(campaign)) => {
+        let progress = self.snake_ctrl.goal_progress(campaign.goal());
+        format!("Goal: {}/{}", progress.current, progress.target)
+      }
+      (None, None) => format!("I: AI {}", ai_name.unwrap_or("off")),
+    };
 
     self.gl.draw(viewport, |c, gl| {
       graphics::clear(*colors::BLACK, gl);
       text::Text::new_color(*colors::WHITE, 32)
         .draw(
-          &format!("Score: {}", score),
+          &score_text,
           glyphs,
           def_draw_state,
           c.transform.trans(30.0, 40.0).zoom(0.5),
@@ -99,12 +262,22 @@ impl<'a> App<'a> {
           )
           .unwrap();
       }
+
+      text::Text::new_color(*colors::WHITE, 32)
+        .draw(
+          &top_right,
+          glyphs,
+          def_draw_state,
+          c.transform.trans(win_width - 150.0, 40.0).zoom(0.5),
+          gl,
+        )
+        .unwrap();
     });
 
     let offset = (50.0, 70.0);
-    let state = self.snake_ctrl.get_full_state_reversed_y();
+    let (dim_x, dim_y) = self.dims;
 
-    let food_sprite = self.sprites.apple();
+    let sprites = &self.sprites;
 
     self.gl.draw(viewport, |c, gl| {
       let r = Rectangle::new(*colors::LIME).border(Border {
@@ -115,30 +288,56 @@ impl<'a> App<'a> {
         [
           0.0,
           0.0,
-          STEP * (BOARD_DIM_X as f64) + 2.0,
-          STEP * (BOARD_DIM_Y as f64) + 2.0,
+          STEP * (dim_x as f64) + 2.0,
+          STEP * (dim_y as f64) + 2.0,
         ],
         def_draw_state,
         c.transform.trans(offset.0 - STEP - 1.0, offset.1 - 1.0),
         gl,
       );
-      for food in &state.food {
-        let x = food.0 as f64 * STEP - HALF_STEP + offset.0;
-        let y = food.1 as f64 * STEP - HALF_STEP + offset.1;
-        food_sprite.draw(c.transform.trans(x, y), gl);
+      let wall = Rectangle::new(*colors::DARK_GREEN);
+      for w in &walls {
+        let x = w.0 as f64 * STEP - HALF_STEP + offset.0;
+        let y = w.1 as f64 * STEP - HALF_STEP + offset.1;
+        wall.draw(
+          [0.0, 0.0, STEP, STEP],
+          def_draw_state,
+          c.transform.trans(x, y),
+          gl,
+        );
+      }
+      let portal = Rectangle::new(*colors::BLUE_OP);
+      for p in &portals {
+        let x = p.0 as f64 * STEP - HALF_STEP + offset.0;
+        let y = p.1 as f64 * STEP - HALF_STEP + offset.1;
+        portal.draw(
+          [0.0, 0.0, STEP, STEP],
+          def_draw_state,
+          c.transform.trans(x, y),
+          gl,
+        );
+      }
+      for food in states.iter().take(1).flat_map(|s| s.food.iter()) {
+        let x = food.point.0 as f64 * STEP - HALF_STEP + offset.0;
+        let y = food.point.1 as f64 * STEP - HALF_STEP + offset.1;
+        sprites.food(food.kind).draw(c.transform.trans(x, y), gl);
       }
     });
 
     let speed_ms = self.timer.get_speed();
+    let help = if self.replay.is_some() {
+      "Space: Play/Pause   Right: Step   Up/Down: Speed"
+    } else if self.online.is_some() {
+      "Esc: Quit   Arrows: Move"
+    } else if self.campaign.is_some() {
+      "Esc: Quit   Space: Pause/Resume"
+    } else {
+      "Esc: Quit   Space: Pause/Resume   M: 1/2 players"
+    };
     self.gl.draw(viewport, |c, gl| {
       let r = Rectangle::new(*colors::GREY);
       r.draw(
-        [
-          0.0,
-          win_height - 20.0,
-          win_width,
-          win_height,
-        ],
+        [0.0, win_height - 20.0, win_width, win_height],
         def_draw_state,
         c.transform.trans(0.0, 0.0),
         gl,
@@ -147,8 +346,8 @@ impl<'a> App<'a> {
       text::Text::new_color(*colors::WHITE, 24)
         .draw(
           &format!(
-            r#"Esc: Quit      Space: Pause/Resume      Size: {}x{}      Speed: {}ms"#,
-            BOARD_DIM_X, BOARD_DIM_Y, speed_ms
+            "{}      Size: {}x{}      Speed: {}ms",
+            help, dim_x, dim_y, speed_ms
           ),
           glyphs,
           def_draw_state,
@@ -158,15 +357,23 @@ impl<'a> App<'a> {
         .unwrap();
     });
 
-    view::draw_snake(
-      &mut self.gl,
-      args.viewport(),
-      &state,
-      &self.sprites,
-      offset,
-    );
+    for (player, state) in players.iter().zip(&states) {
+      let tint = if player.id == 0 {
+        None
+      } else {
+        Some(*colors::BLUE_OP)
+      };
+      view::draw_snake(
+        &mut self.gl,
+        args.viewport(),
+        state,
+        &self.sprites,
+        offset,
+        tint,
+      );
+    }
 
-    if self.is_game_over {
+    if let Some((title, subtitle)) = overlay {
       self.gl.draw(viewport, |c, gl| {
         let r = Rectangle::new(*colors::BLACK_OP);
         r.draw(
@@ -178,7 +385,7 @@ impl<'a> App<'a> {
 
         text::Text::new_color(*colors::WHITE, 64)
           .draw(
-            "Game over :(",
+            &title,
             glyphs,
             def_draw_state,
             c.transform
@@ -190,7 +397,7 @@ impl<'a> App<'a> {
 
         text::Text::new_color(*colors::WHITE, 40)
           .draw(
-            "Press Space to restart",
+            &subtitle,
             glyphs,
             def_draw_state,
             c.transform
@@ -203,35 +410,326 @@ impl<'a> App<'a> {
     }
   }
 
+  /// Players and their snakes to draw, from the local game or from the
+  /// server.
+  fn frame(&self) -> (Vec<PlayerInfo>, Vec<SnakeCtrlFullState>) {
+    if let Some(online) = &self.online {
+      return online.frame();
+    }
+    let players = self.snake_ctrl.players();
+    let states = players
+      .iter()
+      .filter_map(|p| self.snake_ctrl.get_player_full_state(p.id, true).ok())
+      .collect();
+    (players, states)
+  }
+
+  /// Obstacle cells with the y axis reversed like the snakes.
+  fn walls(&self) -> Vec<Point> {
+    let walls = match &self.online {
+      Some(online) => online.walls(),
+      None => self.snake_ctrl.walls().to_vec(),
+    };
+    self.reversed(walls)
+  }
+
+  /// Both cells of every portal, reversed like the walls.
+  fn portals(&self) -> Vec<Point> {
+    let pairs = match &self.online {
+      Some(online) => online.portals(),
+      None => self.snake_ctrl.portals().to_vec(),
+    };
+    self.reversed(pairs.into_iter().flat_map(|(a, b)| vec![a, b]).collect())
+  }
+
+  fn reversed(&self, points: Vec<Point>) -> Vec<Point> {
+    points
+      .into_iter()
+      .map(|mut p| {
+        p.reverse_y(self.dims.1);
+        p
+      })
+      .collect()
+  }
+
+  /// Title and subtitle shown over the board.
+  fn overlay(&self, players: usize) -> Option<(String, String)> {
+    if let Some(online) = &self.online {
+      return online.overlay();
+    }
+    if self.campaign.is_some() {
+      return self.campaign_overlay();
+    }
+    if !self.is_game_over {
+      return None;
+    }
+    let (title, subtitle) = if self.replay.is_some() {
+      ("Replay is over".to_owned(), "Press Esc to quit")
+    } else if players > 1 {
+      let title = match self.winner {
+        Some(id) => format!("Player {} won!", id + 1),
+        None => "Draw".to_owned(),
+      };
+      (title, "Press Space to restart")
+    } else if self.winner.is_some() {
+      ("You won! :)".to_owned(), "Press Space to restart")
+    } else {
+      ("Game over :(".to_owned(), "Press Space to restart")
+    };
+    Some((title, subtitle.to_owned()))
+  }
+
+  fn campaign_overlay(&self) -> Option<(String, String)> {
+    let campaign = self.campaign.as_ref()?;
+    let overlay = match self.screen {
+      Screen::LevelIntro => {
+        let title = match campaign.level().name() {
+          Some(name) => format!("Level {}: {}", campaign.number(), name),
+          None => format!("Level {}", campaign.number()),
+        };
+        (title, format!("{}. Press Space to start", campaign.goal()))
+      }
+      Screen::LevelComplete => (
+        "Level complete!".to_owned(),
+        format!("Press Space for level {}", campaign.number()),
+      ),
+      Screen::CampaignComplete => (
+        "Campaign complete!".to_owned(),
+        format!("All {} levels done. Press Space", campaign.count()),
+      ),
+      Screen::Playing if self.is_game_over => (
+        "Game over :(".to_owned(),
+        "Press Space to try again".to_owned(),
+      ),
+      Screen::Playing => return None,
+    };
+    Some(overlay)
+  }
+
   fn update(&mut self, _args: &UpdateArgs) {
-    match self.snake_ctrl.next_tick() {
-      Ok(is_ate) => {
-        if is_ate {
-          self.score += 1;
-          self.record.set_current_score(self.score);
-          self.timer.decrease_tick_millis();
+    if self.replay.is_some() {
+      self.update_replay();
+      return;
+    }
+
+    if let Some((_, policy)) = &mut self.ai {
+      let direction = policy.decide(&self.snake_ctrl.observe());
+      self.snake_ctrl.direction_to(direction).unwrap();
+    }
+    let is_single = !self.is_two_players();
+
+    let outcome = match self.snake_ctrl.next_tick() {
+      Ok(o) => o,
+      Err(e) => {
+        eprintln!("{:?}", e);
+        return;
+      }
+    };
+
+    for event in &outcome.events {
+      match event {
+        TickEvent::Ate { snake, kind, .. } => {
+          if *snake == 0 {
+            self.score += kind.score();
+          }
+          if is_single {
+            self.record.set_current_score(self.score);
+          }
+          self.apply_speed(*kind);
         }
+        TickEvent::Won(id) => self.winner = Some(*id),
+        TickEvent::GameOver => self.finish_game(is_single),
+        _ => {}
       }
-      Err(e) => match e {
-        SnakeCtrlErr::SnakeAteItself | SnakeCtrlErr::SnakeHitTheWall => {
-          self.is_game_over = true;
-          self.record.write();
-          self.timer.pause();
+    }
+
+    let is_level_complete = match &self.campaign {
+      Some(campaign) => {
+        !self.is_game_over && self.snake_ctrl.is_goal_reached(campaign.goal())
+      }
+      None => false,
+    };
+    if let (true, Some(campaign)) = (is_level_complete, &mut self.campaign) {
+      self.screen = if campaign.complete_level() {
+        Screen::LevelComplete
+      } else {
+        Screen::CampaignComplete
+      };
+      self.finish_game(is_single);
+    }
+  }
+
+  /// Speed items change the tick length, any other food makes the game a
+  /// bit faster.
+  fn apply_speed(&mut self, kind: FoodKind) {
+    let speed = self.timer.get_speed();
+    match kind {
+      FoodKind::SpeedUp => self.timer.set_speed((speed * 3 / 4).max(40)),
+      FoodKind::SlowDown => self.timer.set_speed((speed * 4 / 3).min(400)),
+      _ => self.timer.decrease_tick_millis(),
+    }
+  }
+
+  fn update_replay(&mut self) {
+    let player = match &mut self.replay {
+      Some(p) => p,
+      None => return,
+    };
+    if player.is_finished(&self.snake_ctrl) {
+      self.is_game_over = true;
+      self.timer.pause();
+      return;
+    }
+
+    match player.step(&mut self.snake_ctrl) {
+      Ok(outcome) => {
+        if let Some(kind) = outcome.eaten_kind_by(0) {
+          self.score += kind.score();
         }
-        _ => eprintln!("{:?}", e),
-      },
+      }
+      Err(e) => eprintln!("{:?}", e),
     }
   }
+
+  fn resume_game(&mut self, saved: save::SavedGame) {
+    match SnakeCtrl::from_snapshot(saved.game) {
+      Ok(ctrl) => {
+        self.snake_ctrl = ctrl;
+        self.score = saved.score;
+        self.record.set_current_score(self.score);
+        self.timer = timer::Timer::new(u128::from(saved.tick_millis));
+        self.timer.pause();
+      }
+      Err(e) => eprintln!("{:?}", e),
+    }
+  }
+
+  fn save_game(&self) {
+    if self.is_game_over
+      || self.replay.is_some()
+      || self.online.is_some()
+      || self.campaign.is_some()
+    {
+      return;
+    }
+    save::SavedGame {
+      score: self.score,
+      tick_millis: self.timer.get_speed() as u64,
+      game: self.snake_ctrl.snapshot(),
+    }
+    .write();
+  }
+
+  /// Records only count in single player games.
+  fn finish_game(&mut self, update_record: bool) {
+    if let Some(replay) = self.snake_ctrl.take_recording() {
+      replay::write_last(&replay);
+    }
+    self.is_game_over = true;
+    if update_record {
+      self.record.write();
+    }
+    self.timer.pause();
+  }
+}
+
+/// Options of a local game with the given number of players.
+fn ctrl_options(players: u8) -> SnakeCtrlOptions {
+  SnakeCtrlOptions::default()
+    .dimension_x(BOARD_DIM_X)
+    .dimension_y(BOARD_DIM_Y)
+    .initial_snake_size(10)
+    .players(players)
+    .input_queue_depth(INPUT_QUEUE_DEPTH)
+    .food_spawns(vec![
+      FoodSpawn::new(FoodKind::Apple, 20),
+      FoodSpawn::new(FoodKind::Golden, 3).lifetime(40),
+      FoodSpawn::new(FoodKind::Shrink, 2).lifetime(60),
+      FoodSpawn::new(FoodKind::SpeedUp, 2).lifetime(60),
+      FoodSpawn::new(FoodKind::SlowDown, 2).lifetime(60),
+      FoodSpawn::new(FoodKind::Poison, 2).lifetime(80),
+    ])
+}
+
+/// Value given as `<name> <value>`.
+fn arg_value(name: &str) -> Option<String> {
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    if arg == name {
+      return args.next();
+    }
+  }
+  None
+}
+
+fn has_arg(name: &str) -> bool {
+  std::env::args().skip(1).any(|arg| arg == name)
+}
+
+/// Path given as `--replay <path>`.
+fn replay_arg() -> Option<PathBuf> {
+  arg_value("--replay").map(PathBuf::from)
 }
 
 fn main() {
   let opengl = OpenGL::V3_2;
 
+  let online = arg_value("--connect").map(|addr| {
+    online::Online::connect(addr).unwrap_or_else(|e| {
+      eprintln!("unable to connect: {}", e);
+      std::process::exit(1);
+    })
+  });
+  let campaign = if has_arg("--campaign") {
+    let campaign = campaign::Campaign::load().unwrap_or_else(|e| {
+      eprintln!("unable to load the campaign: {}", e);
+      std::process::exit(1);
+    });
+    Some(campaign)
+  } else {
+    None
+  };
+  let level = match &campaign {
+    Some(campaign) => Some(campaign.level().clone()),
+    None => arg_value("--level").map(|arg| {
+      levels::load(&arg).unwrap_or_else(|e| {
+        eprintln!("unable to load the level: {}", e);
+        std::process::exit(1);
+      })
+    }),
+  };
+  let options = level.as_ref().map_or_else(
+    || ctrl_options(1),
+    |l| l.options().clone().input_queue_depth(INPUT_QUEUE_DEPTH),
+  );
+  let mut snake_ctrl = SnakeCtrl::new(&options).unwrap_or_else(|e| {
+    eprintln!("unable to start the game: {}", e);
+    std::process::exit(1);
+  });
+  let mut replay = None;
+  let mut saved = None;
+  if online.is_none() {
+    if let Some(path) = replay_arg() {
+      let r = replay::read(&path).expect("unable to read the replay");
+      snake_ctrl = r.new_ctrl().unwrap();
+      replay = Some(ReplayPlayer::new(r));
+    } else if level.is_none() {
+      saved = save::SavedGame::take();
+    }
+  }
+  let dims = match (&online, &saved) {
+    (Some(online), _) => online.dimensions(),
+    (None, Some(saved)) => saved.game.dimensions(),
+    (None, None) => snake_ctrl.dimensions(),
+  };
+  let level_speed = level.as_ref().and_then(|l| l.speed()).map(u128::from);
+  let window_dims = campaign.as_ref().map_or(dims, |c| c.dimensions());
+
   let mut window: Window = WindowSettings::new(
     "(Snake game).rs",
     [
-      (BOARD_DIM_X * STEP as u16 + 70) as u32,
-      (BOARD_DIM_Y * STEP as u16 + 130) as u32,
+      (window_dims.0 * STEP as u16 + 70) as u32,
+      (window_dims.1 * STEP as u16 + 130) as u32,
     ],
   )
   .resizable(false)
@@ -240,11 +738,6 @@ fn main() {
   .build()
   .unwrap();
 
-  let snake_ctrl_options = SnakeCtrlOptions::default()
-    .dimension_x(BOARD_DIM_X)
-    .dimension_y(BOARD_DIM_Y)
-    .initial_snake_size(10);
-
   let glyph_cache = GlyphCache::from_bytes(
     include_bytes!("../FiraSans-Regular.ttf"),
     (),
@@ -255,15 +748,34 @@ fn main() {
   let mut app = App {
     gl: GlGraphics::new(opengl),
     score: 0,
-    snake_ctrl: SnakeCtrl::new(&snake_ctrl_options).unwrap(),
+    snake_ctrl,
     glyph_cache,
     sprites: view::Sprites::init(),
-    timer: timer::Timer::new(150),
+    timer: timer::Timer::new(level_speed.unwrap_or(150)),
     record: record::Record::init(),
     is_game_over: false,
+    winner: None,
+    replay,
+    ai: None,
+    online,
+    dims,
+    options,
+    level_speed,
+    campaign,
+    screen: Screen::Playing,
     def_draw_state: DrawState::default(),
   };
 
+  if app.campaign.is_some() {
+    app.start_level();
+  } else if app.online.is_some() || app.replay.is_some() {
+    app.timer.pause();
+  } else if let Some(saved) = saved {
+    app.resume_game(saved);
+  } else {
+    app.snake_ctrl.start_recording();
+  }
+
   let mut events = Events::new(EventSettings::new().max_fps(30));
 
   while let Some(e) = events.next(&mut window) {
@@ -276,9 +788,13 @@ fn main() {
     };
 
     if let Some(args) = e.update_args() {
-      if app.timer.is_ready() {
+      if let Some(online) = &mut app.online {
+        online.update();
+      } else if app.timer.is_ready() {
         app.update(&args);
       }
     }
   }
+
+  app.save_game();
 }
@@ -0,0 +1,138 @@
+use piston::input::Key;
+use snake_ctrl::net::Client;
+use snake_ctrl::{Direction, PlayerInfo, Point, SnakeCtrlFullState};
+use std::io;
+
+/// Updates between two reconnection attempts, about a second.
+static RECONNECT_EVERY: u32 = 60;
+/// Updates between two pings.
+static PING_EVERY: u32 = 30;
+
+/// A game played on a `snake_server`. A lost connection is retried with
+/// the same token, so the player gets its snake back.
+pub struct Online {
+  addr: String,
+  client: Option<Client>,
+  token: u64,
+  player: usize,
+  dims: (u16, u16),
+  updates: u32,
+}
+
+impl Online {
+  pub fn connect(addr: String) -> io::Result<Self> {
+    let client = Client::connect(addr.as_str(), None)?;
+    Ok(Online {
+      addr,
+      token: client.token(),
+      player: client.player(),
+      dims: client.dimensions(),
+      client: Some(client),
+      updates: 0,
+    })
+  }
+
+  pub fn dimensions(&self) -> (u16, u16) {
+    self.dims
+  }
+
+  pub fn handle_key_press(&mut self, key: Key) {
+    let direction = match key {
+      Key::Right => Direction::Right,
+      Key::Left => Direction::Left,
+      Key::Up => Direction::Top,
+      Key::Down => Direction::Bottom,
+      _ => return,
+    };
+    if let Some(client) = &mut self.client {
+      if client.direction_to(direction).is_err() {
+        self.client = None;
+      }
+    }
+  }
+
+  /// Applies what the server sent, reconnects when the connection is lost.
+  pub fn update(&mut self) {
+    self.updates = self.updates.wrapping_add(1);
+    match &mut self.client {
+      Some(client) => {
+        let mut res = client.poll().map(|_| ());
+        if self.updates % PING_EVERY == 0 {
+          res = res.and_then(|_| client.ping());
+        }
+        if let Err(e) = res {
+          eprintln!("connection lost: {}", e);
+          self.client = None;
+        }
+      }
+      None if self.updates % RECONNECT_EVERY == 0 => {
+        match Client::connect(self.addr.as_str(), Some(self.token)) {
+          Ok(client) => self.client = Some(client),
+          Err(e) => eprintln!("unable to reconnect: {}", e),
+        }
+      }
+      None => {}
+    }
+  }
+
+  pub fn frame(&self) -> (Vec<PlayerInfo>, Vec<SnakeCtrlFullState>) {
+    let client = match &self.client {
+      Some(c) => c,
+      None => return (vec![], vec![]),
+    };
+    let players = client.players();
+    let states = players
+      .iter()
+      .filter_map(|p| client.full_state(p.id, true))
+      .collect();
+    (players, states)
+  }
+
+  pub fn walls(&self) -> Vec<Point> {
+    self
+      .client
+      .as_ref()
+      .map_or_else(Vec::new, |c| c.walls().to_vec())
+  }
+
+  pub fn portals(&self) -> Vec<(Point, Point)> {
+    self
+      .client
+      .as_ref()
+      .map_or_else(Vec::new, |c| c.portals().to_vec())
+  }
+
+  pub fn status(&self) -> String {
+    let rtt = self.client.as_ref().and_then(|c| c.rtt());
+    match rtt {
+      Some(rtt) => format!("P{}  ping {}ms", self.player + 1, rtt.as_millis()),
+      None => format!("P{}", self.player + 1),
+    }
+  }
+
+  pub fn overlay(&self) -> Option<(String, String)> {
+    let client = match &self.client {
+      Some(c) => c,
+      None => {
+        return Some(("Connection lost".to_owned(), "Reconnecting...".into()))
+      }
+    };
+    if !client.is_started() {
+      let joined = format!(
+        "{} of {} joined",
+        client.joined(),
+        client.players_expected()
+      );
+      return Some(("Waiting for players".to_owned(), joined));
+    }
+    if !client.is_over() {
+      return None;
+    }
+    let title = match client.winner() {
+      Some(id) if id == self.player => "You won! :)".to_owned(),
+      Some(id) => format!("Player {} won!", id + 1),
+      None => "Game over :(".to_owned(),
+    };
+    Some((title, "Next round starts soon".to_owned()))
+  }
+}
@@ -0,0 +1,27 @@
+use snake_ctrl::Replay;
+use std::fs;
+use std::path::Path;
+
+static LAST_REPLAY_NAME: &str = "snake_rs_last_replay.json";
+
+pub fn read(path: &Path) -> Option<Replay> {
+  let data = fs::read_to_string(path)
+    .map_err(|e| println!("{:?}", e))
+    .ok()?;
+  serde_json::from_str(&data)
+    .map_err(|e| println!("{:?}", e))
+    .ok()
+}
+
+/// Keeps the replay of the last finished game in the data dir.
+pub fn write_last(replay: &Replay) {
+  if let Some(dir) = dirs::data_dir() {
+    serde_json::to_string(replay)
+      .map_err(|e| println!("{:?}", e))
+      .and_then(|data| {
+        fs::write(dir.join(LAST_REPLAY_NAME), data)
+          .map_err(|e| println!("{:?}", e))
+      })
+      .ok();
+  };
+}
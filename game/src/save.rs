@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use snake_ctrl::SnakeCtrlSnapshot;
+use std::fs;
+
+static SAVE_NAME: &str = "snake_rs_game_save.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+  pub score: u64,
+  pub tick_millis: u64,
+  pub game: SnakeCtrlSnapshot,
+}
+
+impl SavedGame {
+  /// Takes the saved game out of the data dir, so it is resumed only once.
+  pub fn take() -> Option<Self> {
+    let path = dirs::data_dir()?.join(SAVE_NAME);
+    let data = fs::read_to_string(&path).ok()?;
+    fs::remove_file(&path).map_err(|e| println!("{:?}", e)).ok();
+    serde_json::from_str(&data)
+      .map_err(|e| println!("{:?}", e))
+      .ok()
+  }
+
+  pub fn write(&self) {
+    if let Some(dir) = dirs::data_dir() {
+      serde_json::to_string(self)
+        .map_err(|e| println!("{:?}", e))
+        .and_then(|data| {
+          fs::write(dir.join(SAVE_NAME), data).map_err(|e| println!("{:?}", e))
+        })
+        .ok();
+    };
+  }
+}
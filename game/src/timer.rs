@@ -50,6 +50,14 @@ impl Timer {
     }
   }
 
+  pub fn set_speed(&mut self, tick_millis: u128) {
+    self.tick_millis = tick_millis;
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.is_paused
+  }
+
   pub fn get_speed(&self) -> u128 {
     self.tick_millis
   }
@@ -13,5 +13,7 @@ lazy_static! {
   pub static ref BLACK_OP: [f32; 4] =
     from_rgb_to_ratio((33.0, 33.0, 33.0, 0.9));
   pub static ref ORANGE: [f32; 4] = from_rgb_to_ratio((224, 93, 31));
+  pub static ref BLUE_OP: [f32; 4] =
+    from_rgb_to_ratio((33.0, 150.0, 243.0, 0.5));
   pub static ref GREY: [f32; 4] = from_rgb_to_ratio((50, 50, 50));
 }
@@ -1,5 +1,5 @@
 use crate::view::Sprites;
-use graphics::{Transformed, Viewport};
+use graphics::{DrawState, Rectangle, Transformed, Viewport};
 use opengl_graphics::GlGraphics;
 use snake_ctrl::{SnakeCtrlFullState, SnakePartVariant};
 
@@ -11,6 +11,7 @@ pub fn draw_snake(
   state: &SnakeCtrlFullState,
   sprites: &Sprites,
   offset: (f64, f64),
+  tint: Option<[f32; 4]>,
 ) {
   gl.draw(vp, |c, gl| {
     for snake_part in &state.snake {
@@ -26,6 +27,14 @@ pub fn draw_snake(
       };
 
       sprite.draw(transform, gl);
+      if let Some(color) = tint {
+        Rectangle::new(color).draw(
+          [0.0, 0.0, STEP, STEP],
+          &DrawState::default(),
+          transform,
+          gl,
+        );
+      }
     }
   });
 }
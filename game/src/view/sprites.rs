@@ -1,5 +1,5 @@
 use opengl_graphics::{Texture, TextureSettings};
-use snake_ctrl::{Direction, SnakeCornerVariant};
+use snake_ctrl::{Direction, FoodKind, SnakeCornerVariant};
 use sprite::Sprite;
 use std::rc::Rc;
 
@@ -8,6 +8,11 @@ static STEP: f64 = 64.0;
 pub struct Sprites {
   // texture: Rc<Texture>,
   apple: Sprite<Texture>,
+  golden: Sprite<Texture>,
+  shrink: Sprite<Texture>,
+  speed_up: Sprite<Texture>,
+  slow_down: Sprite<Texture>,
+  poison: Sprite<Texture>,
   head_top: Sprite<Texture>,
   head_right: Sprite<Texture>,
   head_bottom: Sprite<Texture>,
@@ -38,6 +43,17 @@ impl Sprites {
     let texture = Rc::new(Texture::from_image(&img, &TextureSettings::new()));
 
     let apple = take_sprite_at_pos(&texture, 0, 3);
+    // The sheet has one apple, other food is the same apple tinted.
+    let tinted_apple = |color| {
+      let mut s = take_sprite_at_pos(&texture, 0, 3);
+      s.set_color(color);
+      s
+    };
+    let golden = tinted_apple([1.0, 0.9, 0.2]);
+    let shrink = tinted_apple([0.6, 0.6, 1.0]);
+    let speed_up = tinted_apple([1.0, 0.6, 0.1]);
+    let slow_down = tinted_apple([0.3, 0.9, 1.0]);
+    let poison = tinted_apple([0.5, 0.2, 0.6]);
 
     let head_top = take_sprite_at_pos(&texture, 3, 0);
     let head_right = take_sprite_at_pos(&texture, 4, 0);
@@ -60,6 +76,11 @@ impl Sprites {
     Sprites {
       // texture,
       apple,
+      golden,
+      shrink,
+      speed_up,
+      slow_down,
+      poison,
       head_top,
       head_right,
       head_bottom,
@@ -77,8 +98,15 @@ impl Sprites {
     }
   }
 
-  pub fn apple(&self) -> &Sprite<Texture> {
-    &self.apple
+  pub fn food(&self, kind: FoodKind) -> &Sprite<Texture> {
+    match kind {
+      FoodKind::Apple => &self.apple,
+      FoodKind::Golden => &self.golden,
+      FoodKind::Shrink => &self.shrink,
+      FoodKind::SpeedUp => &self.speed_up,
+      FoodKind::SlowDown => &self.slow_down,
+      FoodKind::Poison => &self.poison,
+    }
   }
 
   pub fn head(&self, direction: Direction) -> &Sprite<Texture> {